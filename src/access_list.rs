@@ -0,0 +1,217 @@
+use std::collections::HashSet;
+
+use ethnum::U256;
+use serde::{Deserialize, Serialize};
+
+use crate::contract::Address;
+
+/// AccessTuple is an entry of the access list of a transaction (EIP-2930): an
+/// account, and some of its storage slots, to warm up before the transaction
+/// runs. In JSON, it is written like in the transactions of the JSON-RPC API:
+/// `{"address": "0x…", "storageKeys": ["0x…"]}`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "RawAccessTuple", into = "RawAccessTuple")]
+pub struct AccessTuple {
+    pub address: Address,
+    pub storage_keys: Vec<U256>,
+}
+
+/// RawAccessTuple is the JSON form of an [`AccessTuple`], in hex.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawAccessTuple {
+    address: String,
+    storage_keys: Vec<String>,
+}
+
+impl TryFrom<RawAccessTuple> for AccessTuple {
+    type Error = String;
+
+    fn try_from(raw: RawAccessTuple) -> Result<Self, Self::Error> {
+        let address = hex::decode(raw.address.trim_start_matches("0x"))
+            .ok()
+            .and_then(|bytes| Address::try_from(bytes).ok())
+            .ok_or_else(|| format!("invalid address {}", raw.address))?;
+        let storage_keys = raw
+            .storage_keys
+            .iter()
+            .map(|key| {
+                U256::from_str_radix(key.trim_start_matches("0x"), 16)
+                    .map_err(|_| format!("invalid storage key {key}"))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(AccessTuple {
+            address,
+            storage_keys,
+        })
+    }
+}
+
+impl From<AccessTuple> for RawAccessTuple {
+    fn from(tuple: AccessTuple) -> Self {
+        RawAccessTuple {
+            address: format!("0x{}", hex::encode(tuple.address)),
+            storage_keys: tuple
+                .storage_keys
+                .iter()
+                .map(|key| format!("0x{}", hex::encode(key.to_be_bytes())))
+                .collect(),
+        }
+    }
+}
+
+/// AccessList is the set of accounts and storage slots accessed during a
+/// transaction (EIP-2929). Accessing them again is cheaper. It is the
+/// counterpart of geth's `accessList`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AccessList {
+    addresses: HashSet<Address>,
+    slots: HashSet<(Address, U256)>,
+}
+
+impl AccessList {
+    pub fn new() -> Self {
+        AccessList::default()
+    }
+
+    /// ContainsAddress returns true if the account is in the list.
+    pub fn contains_address(&self, address: &Address) -> bool {
+        self.addresses.contains(address)
+    }
+
+    /// Contains returns whether the account, and the slot `key` of the
+    /// account, are in the list.
+    pub fn contains(&self, address: &Address, key: &U256) -> (bool, bool) {
+        (
+            self.contains_address(address),
+            self.slots.contains(&(*address, *key)),
+        )
+    }
+
+    /// AddAddress adds the account to the list, and returns true if it was
+    /// not there yet.
+    pub fn add_address(&mut self, address: Address) -> bool {
+        self.addresses.insert(address)
+    }
+
+    /// AddSlot adds the slot `key` of the account to the list, along with the
+    /// account itself. It returns whether each of them was not there yet.
+    pub fn add_slot(&mut self, address: Address, key: U256) -> (bool, bool) {
+        (
+            self.addresses.insert(address),
+            self.slots.insert((address, key)),
+        )
+    }
+
+    /// Tuples returns the accessed accounts and storage slots as the access
+    /// list of a transaction (EIP-2930), sorted so that it is stable. It is
+    /// what geth's `AccessListTracer` generates for `eth_createAccessList`.
+    ///
+    /// The accounts in `exclude` are left out unless some of their slots were
+    /// accessed, since listing them would cost gas for nothing: they are warm
+    /// anyway (e.g. the sender or the precompiles).
+    pub fn tuples(&self, exclude: &[Address]) -> Vec<AccessTuple> {
+        let mut tuples: Vec<_> = self
+            .addresses
+            .iter()
+            .map(|address| AccessTuple {
+                address: *address,
+                storage_keys: self
+                    .slots
+                    .iter()
+                    .filter(|(slot_address, _)| slot_address == address)
+                    .map(|(_, key)| *key)
+                    .collect(),
+            })
+            .filter(|tuple| !tuple.storage_keys.is_empty() || !exclude.contains(&tuple.address))
+            .collect();
+        tuples.sort_by_key(|tuple| tuple.address);
+        for tuple in &mut tuples {
+            tuple.storage_keys.sort();
+        }
+        tuples
+    }
+
+    /// DeleteAddress removes the account from the list. It is only meant to
+    /// undo [`AccessList::add_address`].
+    pub fn delete_address(&mut self, address: &Address) {
+        self.addresses.remove(address);
+    }
+
+    /// DeleteSlot removes the slot `key` of the account from the list, but not
+    /// the account. It is only meant to undo [`AccessList::add_slot`].
+    pub fn delete_slot(&mut self, address: &Address, key: &U256) {
+        self.slots.remove(&(*address, *key));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ethnum::uint;
+
+    use super::*;
+
+    const ALICE: Address = [0xaa; 20];
+
+    #[test]
+    fn can_add_and_delete() {
+        let mut list = AccessList::new();
+        assert!(!list.contains_address(&ALICE));
+        assert!(list.add_address(ALICE));
+        assert!(!list.add_address(ALICE));
+
+        assert_eq!(list.add_slot(ALICE, uint!("1")), (false, true));
+        assert_eq!(list.contains(&ALICE, &uint!("1")), (true, true));
+        assert_eq!(list.contains(&ALICE, &uint!("2")), (true, false));
+
+        list.delete_slot(&ALICE, &uint!("1"));
+        assert_eq!(list.contains(&ALICE, &uint!("1")), (true, false));
+        list.delete_address(&ALICE);
+        assert!(!list.contains_address(&ALICE));
+    }
+
+    #[test]
+    fn can_get_tuples() {
+        let bob = [0xbb; 20];
+        let carol = [0xcc; 20];
+        let mut list = AccessList::new();
+        list.add_slot(bob, uint!("2"));
+        list.add_slot(bob, uint!("1"));
+        list.add_address(ALICE);
+        list.add_address(carol);
+        assert_eq!(
+            list.tuples(&[bob, carol]),
+            vec![
+                AccessTuple {
+                    address: ALICE,
+                    storage_keys: vec![],
+                },
+                AccessTuple {
+                    address: bob,
+                    storage_keys: vec![uint!("1"), uint!("2")],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn can_convert_tuples_to_json() {
+        let json = r#"[{"address":"0xaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa","storageKeys":["0x0000000000000000000000000000000000000000000000000000000000000001"]}]"#;
+        let tuples: Vec<AccessTuple> = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            tuples,
+            vec![AccessTuple {
+                address: ALICE,
+                storage_keys: vec![uint!("1")],
+            }]
+        );
+        assert_eq!(serde_json::to_string(&tuples).unwrap(), json);
+
+        // Short keys are fine, short addresses are not
+        let json = r#"[{"address":"0xaa","storageKeys":["0x01"]}]"#;
+        assert!(serde_json::from_str::<Vec<AccessTuple>>(json).is_err());
+        let json =
+            r#"[{"address":"0xaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa","storageKeys":["0x01"]}]"#;
+        assert!(serde_json::from_str::<Vec<AccessTuple>>(json).is_ok());
+    }
+}
@@ -0,0 +1,182 @@
+use std::{collections::HashMap, rc::Rc};
+
+use ethnum::U256;
+
+use crate::opcodes::Opcode;
+
+/// Bitvec is a bit vector which maps bytes in a program. An unset bit means
+/// the byte is an opcode, a set bit means it is data (i.e. an immediate of a
+/// PUSHn).
+///
+/// This is needed because a JUMPDEST byte can appear as data in the middle of
+/// a PUSH, e.g. in `PUSH1 0x5b`. Jumping there would make the EVM run the
+/// rest of the code from the middle of an instruction, so only the JUMPDESTs
+/// that are real opcodes are valid destinations.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Bitvec {
+    bits: Vec<u8>,
+}
+
+impl Bitvec {
+    fn new(len: usize) -> Self {
+        Bitvec {
+            bits: vec![0; len / 8 + 1],
+        }
+    }
+
+    fn set_data(&mut self, pos: usize) {
+        self.bits[pos / 8] |= 1 << (pos % 8);
+    }
+
+    /// CodeSegment checks if the byte at `pos` is an opcode, as opposed to
+    /// the immediate of a PUSHn.
+    pub fn is_code_segment(&self, pos: usize) -> bool {
+        match self.bits.get(pos / 8) {
+            Some(byte) => byte & (1 << (pos % 8)) == 0,
+            // Past the end of the code, which is implicitly padded with STOPs
+            None => true,
+        }
+    }
+}
+
+/// CodeBitmap collects data locations in code. It walks the code one
+/// instruction at a time, and marks the immediates of every PUSHn as data.
+pub fn code_bitmap(code: &[u8]) -> Bitvec {
+    let mut bits = Bitvec::new(code.len());
+    let mut pc = 0;
+    while pc < code.len() {
+        // Undefined opcodes have no immediate
+        let size = Opcode::try_from(code[pc]).map_or(0, |op| op.push_size());
+        pc += 1;
+        // The immediate of the last PUSHn may be cut short by the end of the
+        // code.
+        for pos in pc..(pc + size).min(code.len()) {
+            bits.set_data(pos);
+        }
+        pc += size;
+    }
+    bits
+}
+
+/// ValidJumpdest checks that `dest` is a JUMPDEST opcode in `code`, and not
+/// the immediate of a PUSHn. `analysis` must be the bitmap of `code`.
+pub fn valid_jumpdest(code: &[u8], analysis: &Bitvec, dest: U256) -> bool {
+    // PC cannot go beyond the length of the code
+    if dest >= code.len() as u128 {
+        return false;
+    }
+    let dest = dest.as_usize();
+    // Only JUMPDESTs allowed for destinations
+    match Opcode::try_from(code[dest]) {
+        Ok(op) if op == Opcode::JUMPDEST => analysis.is_code_segment(dest),
+        _ => false,
+    }
+}
+
+/// JumpDestCache keeps the analysis of every code that was executed, by code
+/// hash, so that repeated calls into the same contract do not analyse its code
+/// again.
+pub struct JumpDestCache {
+    analyses: HashMap<[u8; 32], Rc<Bitvec>>,
+}
+
+impl JumpDestCache {
+    pub fn new() -> Self {
+        JumpDestCache {
+            analyses: HashMap::new(),
+        }
+    }
+
+    /// Analysis returns the bitmap of `code`, whose hash is `code_hash`. The
+    /// code is only analysed the first time its hash is seen.
+    pub fn analysis(&mut self, code_hash: [u8; 32], code: &[u8]) -> Rc<Bitvec> {
+        self.analyses
+            .entry(code_hash)
+            .or_insert_with(|| Rc::new(code_bitmap(code)))
+            .clone()
+    }
+
+    /// Get the number of analyses in the cache
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.analyses.len()
+    }
+}
+
+#[cfg(not(tarpaulin_include))]
+impl Default for JumpDestCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ethnum::uint;
+
+    use super::*;
+
+    #[test]
+    fn can_mark_push_data() {
+        // PUSH1 0x5b, JUMPDEST, PUSH2 0x5b5b, JUMPDEST
+        let code = [0x60, 0x5b, 0x5b, 0x61, 0x5b, 0x5b, 0x5b];
+        let bits = code_bitmap(&code);
+        let segments: Vec<bool> = (0..code.len())
+            .map(|pos| bits.is_code_segment(pos))
+            .collect();
+        assert_eq!(segments, vec![true, false, true, true, false, false, true]);
+    }
+
+    #[test]
+    fn can_mark_push32_data() {
+        let mut code = vec![0x7f];
+        code.extend([0x5b; 32]);
+        code.push(0x5b);
+        let bits = code_bitmap(&code);
+        assert!(bits.is_code_segment(0));
+        assert!((1..33).all(|pos| !bits.is_code_segment(pos)));
+        assert!(bits.is_code_segment(33));
+    }
+
+    #[test]
+    fn can_mark_truncated_push_data() {
+        // PUSH4 0x5b5b, with the code ending in the middle of the immediate
+        let code = [0x63, 0x5b, 0x5b];
+        let bits = code_bitmap(&code);
+        assert!(bits.is_code_segment(0));
+        assert!(!bits.is_code_segment(1));
+        assert!(!bits.is_code_segment(2));
+    }
+
+    #[test]
+    fn can_validate_jumpdest() {
+        // PUSH1 0x5b, JUMPDEST, ADD
+        let code = [0x60, 0x5b, 0x5b, 0x01];
+        let bits = code_bitmap(&code);
+        assert!(valid_jumpdest(&code, &bits, uint!("2")));
+        // Not a JUMPDEST
+        assert!(!valid_jumpdest(&code, &bits, uint!("0")));
+        assert!(!valid_jumpdest(&code, &bits, uint!("3")));
+        // A JUMPDEST byte, but as data
+        assert!(!valid_jumpdest(&code, &bits, uint!("1")));
+        // Out of the code
+        assert!(!valid_jumpdest(&code, &bits, uint!("4")));
+        assert!(!valid_jumpdest(&code, &bits, U256::MAX));
+    }
+
+    #[test]
+    fn can_cache_analysis() {
+        let mut cache = JumpDestCache::new();
+        let code = [0x60, 0x5b, 0x5b];
+        let first = cache.analysis([0x01; 32], &code);
+        // The code is not analysed again, even if it were different
+        let second = cache.analysis([0x01; 32], &[]);
+        assert!(Rc::ptr_eq(&first, &second));
+        assert_eq!(cache.len(), 1);
+
+        let third = cache.analysis([0x02; 32], &code);
+        assert!(!Rc::ptr_eq(&first, &third));
+        assert_eq!(first, third);
+        assert_eq!(cache.len(), 2);
+    }
+}
@@ -0,0 +1,361 @@
+use std::collections::HashMap;
+
+use ethnum::U256;
+use thiserror::Error;
+
+use crate::opcodes::Opcode;
+
+/// Errors of the assembler. Lines are numbered from 1.
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum AsmError {
+    #[error("line {line}: unknown mnemonic {name}")]
+    UnknownMnemonic { line: usize, name: String },
+    #[error("line {line}: {name} expects an immediate")]
+    MissingImmediate { line: usize, name: String },
+    #[error("line {line}: {name} does not take an immediate")]
+    UnexpectedImmediate { line: usize, name: String },
+    #[error("line {line}: invalid immediate {value}")]
+    InvalidImmediate { line: usize, value: String },
+    #[error("line {line}: immediate does not fit in {width} bytes")]
+    ImmediateTooLarge { line: usize, width: usize },
+    #[error("line {line}: label {label} is defined twice")]
+    DuplicateLabel { line: usize, label: String },
+    #[error("line {line}: label {label} is not defined")]
+    UndefinedLabel { line: usize, label: String },
+}
+
+/// The value pushed by a PUSH, which is only known once the code is laid out
+/// if it is a label.
+enum Value {
+    Literal(U256),
+    Label(String),
+}
+
+enum Item {
+    Op(Opcode),
+    Push {
+        line: usize,
+        /// The width of the immediate, i.e. n for PUSHn. It is chosen by the
+        /// assembler for a bare PUSH.
+        width: Option<usize>,
+        value: Value,
+    },
+}
+
+/// Assemble turns mnemonic text into bytecode. It is the inverse of the
+/// [`disassembler`](crate::disasm), and mirrors geth's `asm` compiler.
+///
+/// The source holds one instruction per line, and comments start with `;` or
+/// `//`:
+/// - `ADD` is an opcode, by the name it is printed with.
+/// - `PUSH1 0x80` is a PUSHn with its immediate, in hex or decimal.
+/// - `PUSH 0x80` is a PUSH of the smallest width which fits the immediate.
+/// - `label:` defines a label, and emits the JUMPDEST it points to.
+/// - `PUSH @label` pushes the offset of the label, with the smallest width
+///   which fits it.
+///
+/// A bare PUSH is at least one byte wide, so that the code also runs before
+/// Shanghai: PUSH0 must be written explicitly.
+pub fn assemble(source: &str) -> Result<Vec<u8>, AsmError> {
+    let (mut items, labels) = parse(source)?;
+    let offsets = layout(&mut items, &labels)?;
+
+    let mut code = Vec::new();
+    for item in &items {
+        match item {
+            Item::Op(op) => code.push(op.byte()),
+            Item::Push { line, width, value } => {
+                let value = resolve(value, &labels, &offsets, *line)?;
+                let width = width.expect("widths are chosen by the layout");
+                if immediate_width(value) > width {
+                    return Err(AsmError::ImmediateTooLarge { line: *line, width });
+                }
+                code.push(Opcode::PUSH0.byte() + width as u8);
+                code.extend_from_slice(&value.to_be_bytes()[32 - width..]);
+            }
+        }
+    }
+    Ok(code)
+}
+
+/// Parse splits the source into items, and returns them along with the index
+/// of the JUMPDEST of each label.
+fn parse(source: &str) -> Result<(Vec<Item>, HashMap<String, usize>), AsmError> {
+    let mut items = Vec::new();
+    let mut labels = HashMap::new();
+    for (i, text) in source.lines().enumerate() {
+        let line = i + 1;
+        let text = text.split(';').next().unwrap_or_default();
+        let text = text.split("//").next().unwrap_or_default().trim();
+        if text.is_empty() {
+            continue;
+        }
+
+        if let Some(label) = text.strip_suffix(':') {
+            let label = label.trim().to_string();
+            if labels.insert(label.clone(), items.len()).is_some() {
+                return Err(AsmError::DuplicateLabel { line, label });
+            }
+            items.push(Item::Op(Opcode::JUMPDEST));
+            continue;
+        }
+
+        let mut words = text.split_whitespace();
+        let name = words.next().unwrap_or_default().to_ascii_uppercase();
+        let immediate = words.next();
+        if let Some(extra) = words.next() {
+            return Err(AsmError::InvalidImmediate {
+                line,
+                value: extra.to_string(),
+            });
+        }
+
+        let width = match name.as_str() {
+            "PUSH" => None,
+            _ => {
+                let op = name
+                    .parse::<Opcode>()
+                    .map_err(|_| AsmError::UnknownMnemonic {
+                        line,
+                        name: name.clone(),
+                    })?;
+                if !op.is_push() || op == Opcode::PUSH0 {
+                    if immediate.is_some() {
+                        return Err(AsmError::UnexpectedImmediate { line, name });
+                    }
+                    items.push(Item::Op(op));
+                    continue;
+                }
+                Some(op.push_size())
+            }
+        };
+        let value = match immediate {
+            None => return Err(AsmError::MissingImmediate { line, name }),
+            Some(label) if label.starts_with('@') => Value::Label(label[1..].to_string()),
+            Some(value) => Value::Literal(parse_immediate(value).ok_or_else(|| {
+                AsmError::InvalidImmediate {
+                    line,
+                    value: value.to_string(),
+                }
+            })?),
+        };
+        items.push(Item::Push { line, width, value });
+    }
+    Ok((items, labels))
+}
+
+/// Layout chooses the width of the bare PUSHes, and returns the offset of
+/// every item in the code.
+///
+/// The offset of a label depends on the width of the PUSHes before it, which
+/// may depend on the offset of labels after it. So the PUSHes of labels start
+/// one byte wide, and are widened until the offsets do not change anymore.
+/// Since PUSHes only ever grow, this terminates.
+fn layout(items: &mut [Item], labels: &HashMap<String, usize>) -> Result<Vec<usize>, AsmError> {
+    // Literals have a known width, and labels start with the smallest one.
+    let mut bare = Vec::new();
+    for (i, item) in items.iter_mut().enumerate() {
+        if let Item::Push { width, value, .. } = item {
+            if width.is_none() {
+                bare.push(i);
+                *width = Some(match value {
+                    Value::Literal(value) => immediate_width(*value),
+                    Value::Label(_) => 1,
+                });
+            }
+        }
+    }
+
+    loop {
+        let offsets = offsets(items);
+        let mut changed = false;
+        for &i in &bare {
+            if let Item::Push {
+                line,
+                width,
+                value: value @ Value::Label(_),
+            } = &mut items[i]
+            {
+                let value = resolve(value, labels, &offsets, *line)?;
+                let needed = immediate_width(value);
+                if needed > width.unwrap() {
+                    *width = Some(needed);
+                    changed = true;
+                }
+            }
+        }
+        if !changed {
+            return Ok(offsets);
+        }
+    }
+}
+
+/// Offsets returns the offset of every item in the code, and of the end of
+/// the code.
+fn offsets(items: &[Item]) -> Vec<usize> {
+    let mut offsets = Vec::with_capacity(items.len() + 1);
+    let mut offset = 0;
+    for item in items {
+        offsets.push(offset);
+        offset += match item {
+            Item::Op(_) => 1,
+            Item::Push { width, .. } => 1 + width.unwrap_or(1),
+        };
+    }
+    offsets.push(offset);
+    offsets
+}
+
+fn resolve(
+    value: &Value,
+    labels: &HashMap<String, usize>,
+    offsets: &[usize],
+    line: usize,
+) -> Result<U256, AsmError> {
+    match value {
+        Value::Literal(value) => Ok(*value),
+        Value::Label(label) => match labels.get(label) {
+            Some(&i) => Ok(U256::from(offsets[i] as u64)),
+            None => Err(AsmError::UndefinedLabel {
+                line,
+                label: label.clone(),
+            }),
+        },
+    }
+}
+
+/// ParseImmediate parses a hex (0x-prefixed) or decimal number.
+fn parse_immediate(value: &str) -> Option<U256> {
+    U256::from_str_prefixed(value).ok()
+}
+
+/// ImmediateWidth returns the number of bytes needed to push `value`, which is
+/// at least 1.
+fn immediate_width(value: U256) -> usize {
+    let bytes = (256 - value.leading_zeros() as usize + 7) / 8;
+    bytes.max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        disasm::disasm_text,
+        interpreter::{ExecutionResult, Interpreter},
+    };
+
+    #[test]
+    fn can_assemble() {
+        let code = assemble(
+            "
+            PUSH1 0x80
+            PUSH1 64 ; the free memory pointer
+            MSTORE
+            // An explicit PUSH0
+            PUSH0
+            push 0x1234
+            STOP
+            ",
+        )
+        .unwrap();
+        assert_eq!(
+            code,
+            vec![0x60, 0x80, 0x60, 0x40, 0x52, 0x5f, 0x61, 0x12, 0x34, 0x00]
+        );
+    }
+
+    #[test]
+    fn can_assemble_labels() {
+        let code = assemble(
+            "
+            PUSH @end
+            JUMP
+            INVALID
+            end:
+            PUSH2 @end
+            ",
+        )
+        .unwrap();
+        assert_eq!(
+            disasm_text(&code),
+            "0x0000: PUSH1 0x04\n\
+             0x0002: JUMP\n\
+             0x0003: INVALID\n\
+             0x0004: JUMPDEST\n\
+             0x0005: PUSH2 0x0004\n"
+        );
+    }
+
+    #[test]
+    fn can_widen_label_push() {
+        // With a PUSH1, the label would be at 0x100, which does not fit. With
+        // a PUSH2, it moves to 0x101.
+        let mut source = String::from("PUSH @end\n");
+        source.push_str(&"STOP\n".repeat(254));
+        source.push_str("end:\n");
+        let code = assemble(&source).unwrap();
+        assert_eq!(&code[..3], &[0x61, 0x01, 0x01]);
+        assert_eq!(code[0x101], Opcode::JUMPDEST.byte());
+    }
+
+    #[test]
+    fn can_run_assembled_loop() {
+        // Sum 3 + 2 + 1
+        let code = assemble(
+            "
+            PUSH0
+            PUSH 3
+            loop:
+            SWAP1
+            DUP2
+            ADD
+            SWAP1
+            PUSH 1
+            SWAP1
+            SUB
+            DUP1
+            PUSH @loop
+            JUMPI
+            ",
+        )
+        .unwrap();
+        let mut interpreter = Interpreter::new(&code, 1_000_000);
+        assert_eq!(interpreter.run().unwrap(), ExecutionResult::Stop);
+        assert_eq!(interpreter.stack().data(), &[U256::from(6u8), U256::ZERO]);
+    }
+
+    #[test]
+    fn cannot_assemble_invalid_source() {
+        assert_eq!(
+            assemble("ADD\nFOO"),
+            Err(AsmError::UnknownMnemonic {
+                line: 2,
+                name: "FOO".to_string()
+            })
+        );
+        assert!(matches!(
+            assemble("PUSH1"),
+            Err(AsmError::MissingImmediate { line: 1, .. })
+        ));
+        assert!(matches!(
+            assemble("ADD 0x01"),
+            Err(AsmError::UnexpectedImmediate { line: 1, .. })
+        ));
+        assert!(matches!(
+            assemble("PUSH zz"),
+            Err(AsmError::InvalidImmediate { line: 1, .. })
+        ));
+        assert_eq!(
+            assemble("PUSH1 0x0100"),
+            Err(AsmError::ImmediateTooLarge { line: 1, width: 1 })
+        );
+        assert!(matches!(
+            assemble("PUSH @nowhere"),
+            Err(AsmError::UndefinedLabel { line: 1, .. })
+        ));
+        assert!(matches!(
+            assemble("a:\na:"),
+            Err(AsmError::DuplicateLabel { line: 2, .. })
+        ));
+    }
+}
@@ -0,0 +1,259 @@
+//! `evm` runs a piece of bytecode from the command line, like geth's
+//! `evm run`.
+//!
+//! ```text
+//! evm --code 6001600201 --gas 100000
+//! evm --codefile contract.hex --input 0xa9059cbb --json
+//! evm --code 6001543160 --fork shanghai --coinbase 0xc0ffee --create-access-list
+//! ```
+
+use std::{fs, path::PathBuf, process::ExitCode};
+
+use clap::Parser;
+use ethnum::U256;
+use most_friendly_evm::{
+    access_list::AccessTuple,
+    contract::{Address, Contract},
+    fork::Fork,
+    interpreter::{ExecutionResult, Interpreter},
+    state::StateDB,
+    tracing::Eip3155Logger,
+};
+
+#[derive(Parser)]
+#[command(name = "evm", about = "Run EVM bytecode")]
+struct Args {
+    /// The code to run, in hex
+    #[arg(
+        long,
+        conflicts_with = "codefile",
+        required_unless_present = "codefile"
+    )]
+    code: Option<String>,
+    /// A file holding the code to run, in hex
+    #[arg(long)]
+    codefile: Option<PathBuf>,
+    /// The calldata, in hex
+    #[arg(long, default_value = "")]
+    input: String,
+    /// The gas available to the execution
+    #[arg(long, default_value_t = 10_000_000_000)]
+    gas: u64,
+    /// The wei sent along with the call, in decimal or 0x-prefixed hex
+    #[arg(long, default_value = "0", value_parser = parse_value)]
+    value: U256,
+    /// The address of the caller, in hex
+    #[arg(long, default_value = "0x73656e646572", value_parser = parse_address)]
+    sender: Address,
+    /// The address of the account whose code runs, in hex
+    #[arg(long, default_value = "0x7265636569766572", value_parser = parse_address)]
+    receiver: Address,
+    /// The fork whose rules apply, e.g. `london`, instead of the latest fork
+    #[arg(long, value_parser = parse_fork)]
+    fork: Option<Fork>,
+    /// The address of the block producer, in hex, which is warm since
+    /// Shanghai (EIP-3651)
+    #[arg(long, default_value = "0x00", value_parser = parse_address)]
+    coinbase: Address,
+    /// The access list of the transaction (EIP-2930), as JSON like
+    /// `[{"address": "0x…", "storageKeys": ["0x…"]}]`
+    #[arg(long, default_value = "[]", value_parser = parse_access_list)]
+    access_list: AccessList,
+    /// Print the access list accessed by the execution, which can be sent
+    /// along with the transaction to make it cheaper, like geth's
+    /// `eth_createAccessList`
+    #[arg(long)]
+    create_access_list: bool,
+    /// Print the trace of the execution as EIP-3155 JSON lines
+    #[arg(long)]
+    json: bool,
+}
+
+fn parse_hex(hex: &str) -> Result<Vec<u8>, String> {
+    let hex = hex.trim();
+    let hex = hex.strip_prefix("0x").unwrap_or(hex);
+    hex::decode(hex).map_err(|err| format!("invalid hex: {err}"))
+}
+
+fn parse_value(value: &str) -> Result<U256, String> {
+    U256::from_str_prefixed(value).map_err(|err| format!("invalid value: {err}"))
+}
+
+/// ParseAddress parses an address, left-padding it with zeros if it is
+/// shorter than 20 bytes.
+fn parse_address(address: &str) -> Result<Address, String> {
+    let bytes = parse_hex(address)?;
+    if bytes.len() > 20 {
+        return Err(format!("address is {} bytes long", bytes.len()));
+    }
+    let mut address = Address::default();
+    address[20 - bytes.len()..].copy_from_slice(&bytes);
+    Ok(address)
+}
+
+/// ParseFork parses the name of a fork, regardless of its case.
+fn parse_fork(name: &str) -> Result<Fork, String> {
+    const FORKS: [Fork; 14] = [
+        Fork::Frontier,
+        Fork::Homestead,
+        Fork::TangerineWhistle,
+        Fork::SpuriousDragon,
+        Fork::Byzantium,
+        Fork::Constantinople,
+        Fork::Petersburg,
+        Fork::Istanbul,
+        Fork::Berlin,
+        Fork::London,
+        Fork::Paris,
+        Fork::Shanghai,
+        Fork::Cancun,
+        Fork::Prague,
+    ];
+    FORKS
+        .into_iter()
+        .find(|fork| format!("{fork:?}").eq_ignore_ascii_case(name))
+        .ok_or_else(|| format!("unknown fork {name}"))
+}
+
+/// AccessList wraps the access list so that clap takes it as a single value
+/// rather than a list of values.
+#[derive(Clone)]
+struct AccessList(Vec<AccessTuple>);
+
+fn parse_access_list(json: &str) -> Result<AccessList, String> {
+    serde_json::from_str(json)
+        .map(AccessList)
+        .map_err(|err| format!("invalid access list: {err}"))
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    let code = match (&args.code, &args.codefile) {
+        (Some(code), _) => parse_hex(code),
+        (None, Some(path)) => fs::read_to_string(path)
+            .map_err(|err| format!("cannot read {}: {err}", path.display()))
+            .and_then(|code| parse_hex(&code)),
+        (None, None) => unreachable!("clap requires --code or --codefile"),
+    };
+    let (code, input) = match code.and_then(|code| Ok((code, parse_hex(&args.input)?))) {
+        Ok(parsed) => parsed,
+        Err(err) => {
+            eprintln!("error: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let contract = Contract {
+        caller: args.sender,
+        address: args.receiver,
+        value: args.value,
+        input,
+    };
+    let mut interpreter = Interpreter::new(&code, args.gas)
+        .with_contract(contract)
+        .with_fork(args.fork.unwrap_or(Fork::LATEST));
+    let fork = interpreter.fork();
+    let recipient = interpreter.contract().address;
+    // The code runs as the code of the recipient, e.g. for EXTCODESIZE(ADDRESS)
+    interpreter.state_mut().set_code(&recipient, code.clone());
+    interpreter.state_mut().prepare(
+        fork,
+        &args.sender,
+        &args.coinbase,
+        Some(&recipient),
+        &args.access_list.0,
+    );
+    let result = if args.json {
+        interpreter.run_with_tracer(&mut Eip3155Logger::new(std::io::stdout()))
+    } else {
+        interpreter.run()
+    };
+
+    let (output, error) = match &result {
+        Ok(ExecutionResult::Stop) => (vec![], None),
+        Ok(ExecutionResult::Return(data)) => (data.clone(), None),
+        Ok(ExecutionResult::Revert(data)) => (data.clone(), Some("execution reverted".to_string())),
+        Err(err) => (vec![], Some(err.to_string())),
+    };
+    // The logger already ends the trace with a summary line
+    if !args.json {
+        println!("output: 0x{}", hex::encode(output));
+        println!("gas used: {}", interpreter.gas().used());
+        if let Some(error) = &error {
+            println!("error: {error}");
+        }
+    }
+    if args.create_access_list {
+        // What is warm anyway does not need to be listed
+        let mut exclude = vec![args.sender, recipient];
+        exclude.extend(fork.precompiles());
+        if fork >= Fork::Shanghai {
+            exclude.push(args.coinbase);
+        }
+        let tuples = interpreter.state().accessed().tuples(&exclude);
+        let tuples = serde_json::to_string(&tuples).unwrap();
+        if args.json {
+            println!("{{\"accessList\":{tuples}}}");
+        } else {
+            println!("access list: {tuples}");
+        }
+    }
+    if error.is_some() {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ethnum::uint;
+
+    use super::*;
+
+    #[test]
+    fn can_parse_address() {
+        let mut sender = Address::default();
+        sender[14..].copy_from_slice(b"sender");
+        assert_eq!(parse_address("0x73656e646572").unwrap(), sender);
+        assert_eq!(parse_address("0x00").unwrap(), Address::default());
+        assert_eq!(parse_address(&"ab".repeat(20)).unwrap(), [0xab; 20]);
+        assert!(parse_address(&"ab".repeat(21)).is_err());
+        assert!(parse_address("0xzz").is_err());
+    }
+
+    #[test]
+    fn can_parse_value() {
+        assert_eq!(parse_value("1000").unwrap(), uint!("1000"));
+        assert_eq!(parse_value("0x10").unwrap(), uint!("16"));
+        assert!(parse_value("-1").is_err());
+    }
+
+    #[test]
+    fn can_parse_fork() {
+        assert_eq!(parse_fork("london").unwrap(), Fork::London);
+        assert_eq!(
+            parse_fork("TangerineWhistle").unwrap(),
+            Fork::TangerineWhistle
+        );
+        assert!(parse_fork("osaka").is_err());
+    }
+
+    #[test]
+    fn can_parse_access_list() {
+        let json = format!(
+            r#"[{{"address":"0x{}","storageKeys":["0x01"]}}]"#,
+            "ab".repeat(20)
+        );
+        let AccessList(tuples) = parse_access_list(&json).unwrap();
+        assert_eq!(
+            tuples,
+            vec![AccessTuple {
+                address: [0xab; 20],
+                storage_keys: vec![uint!("1")],
+            }]
+        );
+        assert!(parse_access_list("[]").unwrap().0.is_empty());
+        assert!(parse_access_list(r#"[{"address":"0x01"}]"#).is_err());
+    }
+}
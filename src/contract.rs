@@ -0,0 +1,54 @@
+use ethnum::U256;
+
+/// Address is the 20-byte address of an account.
+pub type Address = [u8; 20];
+
+/// AddressToWord converts an address into the word pushed onto the stack, i.e.
+/// left-padded with zeros.
+pub fn address_to_word(address: &Address) -> U256 {
+    let mut bytes = [0u8; 32];
+    bytes[12..].copy_from_slice(address);
+    U256::from_be_bytes(bytes)
+}
+
+/// WordToAddress converts a word popped from the stack into an address, i.e.
+/// keeps its 20 least significant bytes.
+pub fn word_to_address(word: &U256) -> Address {
+    word.to_be_bytes()[12..].try_into().unwrap()
+}
+
+/// Contract is the call being run by the interpreter: who called which
+/// account, with which value and input. It is the counterpart of geth's
+/// `Contract`, whose code is held by the interpreter.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Contract {
+    /// The account which made the call (CALLER)
+    pub caller: Address,
+    /// The account whose code runs (ADDRESS)
+    pub address: Address,
+    /// The wei sent along with the call (CALLVALUE)
+    pub value: U256,
+    /// The calldata of the call
+    pub input: Vec<u8>,
+}
+
+#[cfg(test)]
+mod tests {
+    use ethnum::uint;
+
+    use super::*;
+
+    #[test]
+    fn can_convert_address_to_word() {
+        let mut address = [0u8; 20];
+        address[0] = 0x12;
+        address[19] = 0x34;
+        assert_eq!(
+            address_to_word(&address),
+            uint!("0x1200000000000000000000000000000000000034")
+        );
+        assert_eq!(word_to_address(&address_to_word(&address)), address);
+        // The upper bytes are ignored
+        assert_eq!(word_to_address(&U256::MAX), [0xff; 20]);
+    }
+}
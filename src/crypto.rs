@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+
+use ethnum::U256;
+
+/// Hasher computes the Keccak-256 hashes of KECCAK256. It is the counterpart
+/// of geth's `crypto.KeccakState`.
+///
+/// The default implementation is [`TinyKeccak`], a pure-Rust Keccak. Enabling
+/// the `sha3` feature makes `Sha3Keccak` the default instead.
+pub trait Hasher {
+    /// Keccak256 returns the Keccak-256 hash of `data`. This is the original
+    /// Keccak, not the SHA3-256 standardized by NIST.
+    fn keccak256(&self, data: &[u8]) -> [u8; 32];
+}
+
+/// TinyKeccak hashes with the `tiny-keccak` crate.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TinyKeccak;
+
+impl Hasher for TinyKeccak {
+    fn keccak256(&self, data: &[u8]) -> [u8; 32] {
+        use tiny_keccak::Hasher as _;
+
+        let mut keccak = tiny_keccak::Keccak::v256();
+        let mut hash = [0u8; 32];
+        keccak.update(data);
+        keccak.finalize(&mut hash);
+        hash
+    }
+}
+
+/// Sha3Keccak hashes with the `sha3` crate of RustCrypto.
+#[cfg(feature = "sha3")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Sha3Keccak;
+
+#[cfg(feature = "sha3")]
+impl Hasher for Sha3Keccak {
+    fn keccak256(&self, data: &[u8]) -> [u8; 32] {
+        use sha3::Digest;
+
+        sha3::Keccak256::digest(data).into()
+    }
+}
+
+/// The hasher used unless another one is given to the interpreter.
+#[cfg(not(feature = "sha3"))]
+pub type DefaultHasher = TinyKeccak;
+/// The hasher used unless another one is given to the interpreter.
+#[cfg(feature = "sha3")]
+pub type DefaultHasher = Sha3Keccak;
+
+/// Keccak256 returns the Keccak-256 hash of `data`, with the
+/// [`DefaultHasher`].
+pub fn keccak256(data: &[u8]) -> [u8; 32] {
+    DefaultHasher::default().keccak256(data)
+}
+
+/// PreimageCache remembers the data each hash was computed from, like geth's
+/// preimage recording. Contracts derive the keys of mappings and dynamic
+/// arrays by hashing, so this is how tools recover what a storage slot stands
+/// for.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PreimageCache {
+    preimages: HashMap<U256, Vec<u8>>,
+}
+
+impl PreimageCache {
+    pub fn new() -> Self {
+        PreimageCache::default()
+    }
+
+    /// Insert records that `hash` is the hash of `preimage`.
+    pub fn insert(&mut self, hash: U256, preimage: &[u8]) {
+        self.preimages
+            .entry(hash)
+            .or_insert_with(|| preimage.to_vec());
+    }
+
+    /// Get returns the data `hash` was computed from, if it was recorded.
+    pub fn get(&self, hash: &U256) -> Option<&[u8]> {
+        self.preimages.get(hash).map(Vec::as_slice)
+    }
+
+    /// Get the number of recorded preimages
+    pub fn len(&self) -> usize {
+        self.preimages.len()
+    }
+
+    /// Check if no preimage was recorded
+    pub fn is_empty(&self) -> bool {
+        self.preimages.is_empty()
+    }
+
+    /// Iter returns the recorded hashes along with their preimages, in no
+    /// particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&U256, &[u8])> {
+        self.preimages
+            .iter()
+            .map(|(hash, preimage)| (hash, preimage.as_slice()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ethnum::uint;
+
+    use super::*;
+
+    const EMPTY_HASH: &str = "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470";
+
+    #[test]
+    fn can_hash() {
+        assert_eq!(hex::encode(TinyKeccak.keccak256(&[])), EMPTY_HASH);
+        assert_eq!(
+            hex::encode(keccak256(&[0; 32])),
+            "290decd9548b62a8d60345a988386fc84ba6bc95484008f6362f93160ef3e563"
+        );
+    }
+
+    #[cfg(feature = "sha3")]
+    #[test]
+    fn can_hash_with_sha3() {
+        assert_eq!(hex::encode(Sha3Keccak.keccak256(&[])), EMPTY_HASH);
+        assert_eq!(
+            Sha3Keccak.keccak256(b"most friendly evm"),
+            TinyKeccak.keccak256(b"most friendly evm")
+        );
+    }
+
+    #[test]
+    fn can_record_preimages() {
+        let mut cache = PreimageCache::new();
+        assert!(cache.is_empty());
+        let hash = U256::from_be_bytes(keccak256(&[0; 32]));
+        cache.insert(hash, &[0; 32]);
+        cache.insert(hash, &[0; 32]);
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.get(&hash), Some(&[0u8; 32][..]));
+        assert_eq!(cache.get(&uint!("1")), None);
+        assert_eq!(cache.iter().count(), 1);
+    }
+}
@@ -0,0 +1,182 @@
+use std::fmt::{self, Write};
+
+use serde::{ser::SerializeStruct, Serialize, Serializer};
+
+use crate::opcodes::Opcode;
+
+/// Instruction is a single entry of a disassembly: the byte at `pc`, decoded
+/// into an opcode, along with its immediate if it is a PUSHn.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Instruction {
+    /// The offset of the instruction in the code
+    pub pc: usize,
+    /// The byte at `pc`
+    pub byte: u8,
+    /// The opcode of `byte`, or None if it is not a valid opcode
+    pub opcode: Option<Opcode>,
+    /// The immediate following the opcode. It is shorter than the PUSHn
+    /// expects if the code ends in the middle of it.
+    pub immediate: Vec<u8>,
+}
+
+impl Instruction {
+    /// IsInvalid returns true if the byte is not a valid opcode.
+    pub fn is_invalid(&self) -> bool {
+        self.opcode.is_none()
+    }
+
+    /// IsTruncated returns true if the code ends before the end of the
+    /// immediate of the PUSHn.
+    pub fn is_truncated(&self) -> bool {
+        self.opcode
+            .map_or(false, |op| self.immediate.len() < op.push_size())
+    }
+}
+
+/// Disasm splits `code` into instructions, skipping over the immediates of the
+/// PUSHn opcodes. Bytes which are not valid opcodes are kept as invalid
+/// instructions, since they are valid code until they are executed.
+pub fn disasm(code: &[u8]) -> Vec<Instruction> {
+    let mut instructions = Vec::new();
+    let mut pc = 0;
+    while pc < code.len() {
+        let byte = code[pc];
+        let opcode = Opcode::try_from(byte).ok();
+        let size = opcode.map_or(0, |op| op.push_size());
+        let start = pc + 1;
+        let end = (start + size).min(code.len());
+        instructions.push(Instruction {
+            pc,
+            byte,
+            opcode,
+            immediate: code[start..end].to_vec(),
+        });
+        pc = start + size;
+    }
+    instructions
+}
+
+/// DisasmText renders the disassembly of `code` as text, one instruction per
+/// line, e.g. `0x0004: PUSH1 0x80`.
+pub fn disasm_text(code: &[u8]) -> String {
+    let mut text = String::new();
+    for instruction in disasm(code) {
+        // Writing to a String cannot fail
+        writeln!(text, "{instruction}").unwrap();
+    }
+    text
+}
+
+/// DisasmJson renders the disassembly of `code` as a JSON array of
+/// instructions.
+pub fn disasm_json(code: &[u8]) -> String {
+    serde_json::to_string_pretty(&disasm(code)).expect("instructions serialize to JSON")
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#06x}: ", self.pc)?;
+        match self.opcode {
+            Some(op) => write!(f, "{op}")?,
+            None => write!(f, "{:#04x}", self.byte)?,
+        }
+        if !self.immediate.is_empty() {
+            write!(f, " 0x{}", hex::encode(&self.immediate))?;
+        }
+        if self.is_invalid() {
+            write!(f, " (invalid opcode)")?;
+        }
+        if self.is_truncated() {
+            write!(f, " (truncated)")?;
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for Instruction {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Instruction", 6)?;
+        state.serialize_field("pc", &self.pc)?;
+        state.serialize_field("byte", &format!("{:#04x}", self.byte))?;
+        state.serialize_field("opcode", &self.opcode.map(|op| op.to_string()))?;
+        let immediate =
+            (!self.immediate.is_empty()).then(|| format!("0x{}", hex::encode(&self.immediate)));
+        state.serialize_field("immediate", &immediate)?;
+        state.serialize_field("invalid", &self.is_invalid())?;
+        state.serialize_field("truncated", &self.is_truncated())?;
+        state.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_disasm() {
+        // PUSH1 0x80, PUSH1 0x40, MSTORE, PUSH0, STOP
+        let code = [0x60, 0x80, 0x60, 0x40, 0x52, 0x5f, 0x00];
+        let instructions = disasm(&code);
+        assert_eq!(instructions.len(), 5);
+        assert_eq!(instructions[1].pc, 2);
+        assert_eq!(instructions[1].opcode, Some(Opcode::PUSH1));
+        assert_eq!(instructions[1].immediate, vec![0x40]);
+        assert!(instructions[3].immediate.is_empty());
+        assert_eq!(
+            disasm_text(&code),
+            "0x0000: PUSH1 0x80\n\
+             0x0002: PUSH1 0x40\n\
+             0x0004: MSTORE\n\
+             0x0005: PUSH0\n\
+             0x0006: STOP\n"
+        );
+    }
+
+    #[test]
+    fn can_flag_invalid_opcode() {
+        // ADD, an undefined byte, STOP
+        let instructions = disasm(&[0x01, 0x0c, 0x00]);
+        assert!(!instructions[0].is_invalid());
+        assert!(instructions[1].is_invalid());
+        assert_eq!(instructions[2].pc, 2);
+        assert_eq!(instructions[1].to_string(), "0x0001: 0x0c (invalid opcode)");
+    }
+
+    #[test]
+    fn can_flag_truncated_push() {
+        // PUSH3 0x0102, with the code ending in the middle of the immediate
+        let instructions = disasm(&[0x62, 0x01, 0x02]);
+        assert_eq!(instructions.len(), 1);
+        assert!(instructions[0].is_truncated());
+        assert_eq!(
+            instructions[0].to_string(),
+            "0x0000: PUSH3 0x0102 (truncated)"
+        );
+
+        let instructions = disasm(&[0x60]);
+        assert!(instructions[0].is_truncated());
+        assert_eq!(instructions[0].to_string(), "0x0000: PUSH1 (truncated)");
+    }
+
+    #[test]
+    fn can_disasm_to_json() {
+        let json: serde_json::Value = serde_json::from_str(&disasm_json(&[0x61, 0x01])).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([
+                {
+                    "pc": 0,
+                    "byte": "0x61",
+                    "opcode": "PUSH2",
+                    "immediate": "0x01",
+                    "invalid": false,
+                    "truncated": true,
+                },
+            ])
+        );
+
+        let json: serde_json::Value = serde_json::from_str(&disasm_json(&[0xfc])).unwrap();
+        assert_eq!(json[0]["opcode"], serde_json::Value::Null);
+        assert_eq!(json[0]["invalid"], true);
+    }
+}
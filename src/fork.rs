@@ -0,0 +1,53 @@
+use crate::contract::Address;
+
+/// Fork is a hard fork of Ethereum mainnet that changed the behaviour of the
+/// EVM. Forks are ordered chronologically, so that `fork >= Fork::Berlin`
+/// checks whether the rules of Berlin are active.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Fork {
+    Frontier,
+    Homestead,
+    TangerineWhistle,
+    SpuriousDragon,
+    Byzantium,
+    Constantinople,
+    Petersburg,
+    Istanbul,
+    Berlin,
+    London,
+    Paris,
+    Shanghai,
+    Cancun,
+    Prague,
+}
+
+impl Fork {
+    /// The latest fork, whose rules apply unless another fork is chosen.
+    pub const LATEST: Fork = Fork::Prague;
+
+    /// Precompiles returns the addresses of the precompiled contracts of the
+    /// fork. They are numbered from 1, and each fork only ever adds some.
+    pub fn precompiles(&self) -> Vec<Address> {
+        let count = match self {
+            Fork::Prague => 0x11,
+            Fork::Cancun => 0x0a,
+            _ if *self >= Fork::Istanbul => 0x09,
+            _ if *self >= Fork::Byzantium => 0x08,
+            _ => 0x04,
+        };
+        (1..=count)
+            .map(|i| {
+                let mut address = Address::default();
+                address[19] = i;
+                address
+            })
+            .collect()
+    }
+}
+
+#[cfg(not(tarpaulin_include))]
+impl Default for Fork {
+    fn default() -> Self {
+        Fork::LATEST
+    }
+}
@@ -0,0 +1,780 @@
+use ethnum::U256;
+use thiserror::Error;
+
+use crate::{contract::Address, fork::Fork, opcodes::Opcode, state::StateDB};
+
+// Gas tiers of the opcodes with a fixed cost. Most opcodes cost one of these.
+// The names mirror `GasQuickStep` etc. in geth's `gas.go`.
+pub const GAS_QUICK_STEP: u64 = 2;
+pub const GAS_FASTEST_STEP: u64 = 3;
+pub const GAS_FAST_STEP: u64 = 5;
+pub const GAS_MID_STEP: u64 = 8;
+pub const GAS_SLOW_STEP: u64 = 10;
+pub const GAS_EXT_STEP: u64 = 20;
+
+/// GasTier is the class of the static cost of an opcode. Most opcodes cost
+/// one of the fixed tiers, the others have a cost of their own, which may
+/// depend on the fork (e.g. BALANCE or SLOAD).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GasTier {
+    Zero,
+    Quick,
+    Fastest,
+    Fast,
+    Mid,
+    Slow,
+    Ext,
+    Special,
+}
+
+impl GasTier {
+    /// Gas returns the cost of the tier, or None for [`GasTier::Special`].
+    pub fn gas(&self) -> Option<u64> {
+        match self {
+            GasTier::Zero => Some(0),
+            GasTier::Quick => Some(GAS_QUICK_STEP),
+            GasTier::Fastest => Some(GAS_FASTEST_STEP),
+            GasTier::Fast => Some(GAS_FAST_STEP),
+            GasTier::Mid => Some(GAS_MID_STEP),
+            GasTier::Slow => Some(GAS_SLOW_STEP),
+            GasTier::Ext => Some(GAS_EXT_STEP),
+            GasTier::Special => None,
+        }
+    }
+}
+
+/// Cost of JUMPDEST.
+pub const JUMPDEST_GAS: u64 = 1;
+/// Base cost of KECCAK256, on top of which each hashed word is charged.
+pub const KECCAK256_GAS: u64 = 30;
+/// Cost of each word hashed by KECCAK256.
+pub const KECCAK256_WORD_GAS: u64 = 6;
+/// Cost of accessing an account or a storage slot that was already accessed
+/// in the transaction (EIP-2929).
+pub const WARM_STORAGE_READ_COST: u64 = 100;
+/// Cost of CREATE and CREATE2.
+pub const CREATE_GAS: u64 = 32000;
+/// Cost of SELFDESTRUCT (EIP-150).
+pub const SELFDESTRUCT_GAS: u64 = 5000;
+/// Cost of each byte of the exponent of EXP (EIP-160).
+pub const EXP_BYTE_GAS: u64 = 50;
+/// Cost of each byte of the exponent of EXP before Spurious Dragon.
+pub const EXP_BYTE_GAS_FRONTIER: u64 = 10;
+
+// Costs of the opcodes accessing accounts or storage before Berlin, which
+// were repriced by Tangerine Whistle (EIP-150) and Istanbul (EIP-1884). The
+// names mirror geth's `params/protocol_params.go`.
+pub const BALANCE_GAS_FRONTIER: u64 = 20;
+pub const BALANCE_GAS_EIP150: u64 = 400;
+pub const BALANCE_GAS_EIP1884: u64 = 700;
+pub const EXTCODE_SIZE_GAS_FRONTIER: u64 = 20;
+pub const EXTCODE_SIZE_GAS_EIP150: u64 = 700;
+pub const EXTCODE_COPY_BASE_FRONTIER: u64 = 20;
+pub const EXTCODE_COPY_BASE_EIP150: u64 = 700;
+pub const EXTCODE_HASH_GAS_CONSTANTINOPLE: u64 = 400;
+pub const EXTCODE_HASH_GAS_EIP1884: u64 = 700;
+pub const SLOAD_GAS_FRONTIER: u64 = 50;
+pub const SLOAD_GAS_EIP150: u64 = 200;
+pub const SLOAD_GAS_EIP1884: u64 = 800;
+pub const CALL_GAS_FRONTIER: u64 = 40;
+pub const CALL_GAS_EIP150: u64 = 700;
+/// Paid by CALL and CALLCODE when they transfer value
+pub const CALL_VALUE_TRANSFER_GAS: u64 = 9000;
+/// Paid by CALL when it brings a new account into existence
+pub const CALL_NEW_ACCOUNT_GAS: u64 = 25000;
+
+// Costs and refunds of SSTORE, under each of its gas schedules: the original
+// one, the net metering of Constantinople (EIP-1283) and Istanbul (EIP-2200),
+// and its repricing by Berlin (EIP-2929) and London (EIP-3529).
+pub const SSTORE_SET_GAS: u64 = 20000;
+pub const SSTORE_RESET_GAS: u64 = 5000;
+pub const SSTORE_REFUND_GAS: u64 = 15000;
+pub const NET_SSTORE_NOOP_GAS: u64 = 200;
+pub const NET_SSTORE_INIT_GAS: u64 = 20000;
+pub const NET_SSTORE_CLEAN_GAS: u64 = 5000;
+pub const NET_SSTORE_DIRTY_GAS: u64 = 200;
+pub const NET_SSTORE_CLEAR_REFUND: u64 = 15000;
+pub const NET_SSTORE_RESET_REFUND: u64 = 4800;
+pub const NET_SSTORE_RESET_CLEAR_REFUND: u64 = 19800;
+/// SSTORE fails if no more than this gas is left, so that it cannot run in a
+/// call given the 2300 gas stipend (EIP-2200).
+pub const SSTORE_SENTRY_GAS_EIP2200: u64 = 2300;
+pub const SLOAD_GAS_EIP2200: u64 = 800;
+pub const SSTORE_SET_GAS_EIP2200: u64 = 20000;
+pub const SSTORE_RESET_GAS_EIP2200: u64 = 5000;
+pub const SSTORE_CLEARS_SCHEDULE_REFUND_EIP2200: u64 = 15000;
+/// Cost of accessing a storage slot for the first time in the transaction
+/// (EIP-2929).
+pub const COLD_SLOAD_COST: u64 = 2100;
+/// Cost of accessing an account for the first time in the transaction
+/// (EIP-2929).
+pub const COLD_ACCOUNT_ACCESS_COST: u64 = 2600;
+/// Refund for clearing a storage slot since London (EIP-3529), which is
+/// `SSTORE_RESET_GAS_EIP2200 - COLD_SLOAD_COST + ACCESS_LIST_STORAGE_KEY_COST`.
+pub const SSTORE_CLEARS_SCHEDULE_REFUND_EIP3529: u64 = 4800;
+/// The refund is capped to the gas used divided by this quotient.
+pub const REFUND_QUOTIENT: u64 = 2;
+/// The refund quotient since London (EIP-3529).
+pub const REFUND_QUOTIENT_EIP3529: u64 = 5;
+
+/// Cost of each word copied by the copy opcodes (e.g. MCOPY).
+pub const COPY_GAS: u64 = 3;
+/// Linear cost of each word of memory.
+pub const MEMORY_GAS: u64 = 3;
+/// Divisor of the quadratic cost of memory.
+pub const QUAD_COEFF_DIV: u64 = 512;
+/// The largest memory size whose cost can be computed without overflowing a
+/// u64. This is the largest `x` such that `x/32 * x/32` fits in a u64,
+/// rounded down to a word.
+pub const MAX_MEMORY_SIZE: u64 = 0x1FFFFFFFE0;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[error("out of gas: {required} required but only {remaining} remaining")]
+pub struct OutOfGas {
+    required: u64,
+    remaining: u64,
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[error("gas uint64 overflow")]
+pub struct GasUintOverflow;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[error("not enough gas for reentrancy sentry")]
+pub struct ReentrancySentry;
+
+/// ExpGas returns the dynamic cost of EXP, which is proportional to the number
+/// of bytes of the exponent. The cost per byte went up in Spurious Dragon
+/// (EIP-160).
+pub fn exp_gas(exponent: &U256, fork: Fork) -> u64 {
+    let bytes = (256 - exponent.leading_zeros() as u64 + 7) / 8;
+    if fork >= Fork::SpuriousDragon {
+        bytes * EXP_BYTE_GAS
+    } else {
+        bytes * EXP_BYTE_GAS_FRONTIER
+    }
+}
+
+/// CopyGas returns the dynamic cost of copying `len` bytes, which is
+/// proportional to the number of words copied.
+///
+/// `len` must fit in a u64, which is guaranteed once the memory size of the
+/// opcode was computed.
+pub fn copy_gas(len: &U256) -> u64 {
+    to_word_size(len.as_u64()) * COPY_GAS
+}
+
+/// Keccak256Gas returns the dynamic cost of hashing `len` bytes, which is
+/// proportional to the number of words hashed.
+///
+/// Like [`copy_gas`], `len` must fit in a u64.
+pub fn keccak256_gas(len: &U256) -> u64 {
+    to_word_size(len.as_u64()) * KECCAK256_WORD_GAS
+}
+
+/// SloadGas returns the dynamic cost of SLOAD, which only exists since Berlin
+/// (EIP-2929): a slot costs more the first time it is accessed, after which
+/// it is warm.
+pub fn sload_gas(state: &mut impl StateDB, address: &Address, key: &U256, fork: Fork) -> u64 {
+    if fork < Fork::Berlin {
+        return 0;
+    }
+    if state.slot_in_access_list(address, key).1 {
+        return WARM_STORAGE_READ_COST;
+    }
+    state.add_slot_to_access_list(address, *key);
+    COLD_SLOAD_COST
+}
+
+/// AccountAccessGas returns the dynamic cost of accessing an account, which
+/// only exists since Berlin (EIP-2929). The warm cost is the constant cost of
+/// the opcodes accessing accounts (BALANCE, EXTCODE* and the CALL family), so
+/// this is the surcharge for the first access, after which the account is
+/// warm. It is geth's `gasEip2929AccountCheck`.
+pub fn account_access_gas(state: &mut impl StateDB, address: &Address, fork: Fork) -> u64 {
+    if fork < Fork::Berlin || state.address_in_access_list(address) {
+        return 0;
+    }
+    state.add_address_to_access_list(address);
+    COLD_ACCOUNT_ACCESS_COST - WARM_STORAGE_READ_COST
+}
+
+/// CallGas returns the dynamic cost of the CALL family calling `address` with
+/// `value` (which is ignored by DELEGATECALL and STATICCALL), without the
+/// memory expansion nor the gas handed to the callee. It is geth's `gasCall`
+/// and friends, with the cold surcharge of `gasCallEIP2929`.
+///
+/// CALL pays for the value it transfers, and for creating the callee if it
+/// does not exist (since Spurious Dragon, if it is empty and receives value,
+/// EIP-161). CALLCODE only pays for the value, since it runs the code of the
+/// callee in the account of the caller.
+pub fn call_gas(
+    state: &mut impl StateDB,
+    op: Opcode,
+    address: &Address,
+    value: &U256,
+    fork: Fork,
+) -> u64 {
+    let mut gas = account_access_gas(state, address, fork);
+    let transfers_value = matches!(op, Opcode::CALL | Opcode::CALLCODE) && *value != 0;
+    if op == Opcode::CALL {
+        let new_account = if fork >= Fork::SpuriousDragon {
+            transfers_value && state.empty(address)
+        } else {
+            !state.exist(address)
+        };
+        if new_account {
+            gas += CALL_NEW_ACCOUNT_GAS;
+        }
+    }
+    if transfers_value {
+        gas += CALL_VALUE_TRANSFER_GAS;
+    }
+    gas
+}
+
+/// SstoreGas returns the dynamic cost of SSTORE writing `value` to the slot
+/// `key`, and updates the refund counter. It mirrors the `gasSStore` functions
+/// of geth, one per gas schedule.
+///
+/// Since Istanbul, the cost depends on the value the slot had at the start of
+/// the transaction (the original value), its current value and the new one,
+/// so that writing a slot several times only costs as much as writing it once.
+/// It also fails if `gas_left` is within the call stipend.
+pub fn sstore_gas(
+    state: &mut impl StateDB,
+    address: &Address,
+    key: &U256,
+    value: &U256,
+    gas_left: u64,
+    fork: Fork,
+) -> Result<u64, ReentrancySentry> {
+    match fork {
+        Fork::Constantinople => Ok(sstore_gas_eip1283(state, address, key, value)),
+        Fork::Istanbul => sstore_gas_eip2200(state, address, key, value, gas_left),
+        Fork::Berlin => sstore_gas_eip2929(
+            state,
+            address,
+            key,
+            value,
+            gas_left,
+            SSTORE_CLEARS_SCHEDULE_REFUND_EIP2200,
+        ),
+        _ if fork >= Fork::London => sstore_gas_eip2929(
+            state,
+            address,
+            key,
+            value,
+            gas_left,
+            SSTORE_CLEARS_SCHEDULE_REFUND_EIP3529,
+        ),
+        _ => Ok(sstore_gas_legacy(state, address, key, value)),
+    }
+}
+
+/// SstoreGasLegacy is the original schedule, which only looks at the current
+/// value of the slot.
+fn sstore_gas_legacy(state: &mut impl StateDB, address: &Address, key: &U256, value: &U256) -> u64 {
+    let current = state.get_state(address, key);
+    if current == 0 && *value != 0 {
+        SSTORE_SET_GAS
+    } else if current != 0 && *value == 0 {
+        state.add_refund(SSTORE_REFUND_GAS);
+        SSTORE_RESET_GAS
+    } else {
+        SSTORE_RESET_GAS
+    }
+}
+
+/// SstoreGasEip1283 is the net metering of Constantinople, which Petersburg
+/// removed.
+fn sstore_gas_eip1283(
+    state: &mut impl StateDB,
+    address: &Address,
+    key: &U256,
+    value: &U256,
+) -> u64 {
+    let current = state.get_state(address, key);
+    if current == *value {
+        return NET_SSTORE_NOOP_GAS;
+    }
+    let original = state.get_committed_state(address, key);
+    if original == current {
+        if original == 0 {
+            return NET_SSTORE_INIT_GAS;
+        }
+        if *value == 0 {
+            state.add_refund(NET_SSTORE_CLEAR_REFUND);
+        }
+        return NET_SSTORE_CLEAN_GAS;
+    }
+    if original != 0 {
+        if current == 0 {
+            state.sub_refund(NET_SSTORE_CLEAR_REFUND);
+        } else if *value == 0 {
+            state.add_refund(NET_SSTORE_CLEAR_REFUND);
+        }
+    }
+    if original == *value {
+        if original == 0 {
+            state.add_refund(NET_SSTORE_RESET_CLEAR_REFUND);
+        } else {
+            state.add_refund(NET_SSTORE_RESET_REFUND);
+        }
+    }
+    NET_SSTORE_DIRTY_GAS
+}
+
+/// SstoreGasEip2200 is the net metering of Istanbul, which brings back
+/// EIP-1283 with the reentrancy sentry.
+fn sstore_gas_eip2200(
+    state: &mut impl StateDB,
+    address: &Address,
+    key: &U256,
+    value: &U256,
+    gas_left: u64,
+) -> Result<u64, ReentrancySentry> {
+    if gas_left <= SSTORE_SENTRY_GAS_EIP2200 {
+        return Err(ReentrancySentry);
+    }
+    let current = state.get_state(address, key);
+    if current == *value {
+        return Ok(SLOAD_GAS_EIP2200);
+    }
+    let original = state.get_committed_state(address, key);
+    if original == current {
+        if original == 0 {
+            return Ok(SSTORE_SET_GAS_EIP2200);
+        }
+        if *value == 0 {
+            state.add_refund(SSTORE_CLEARS_SCHEDULE_REFUND_EIP2200);
+        }
+        return Ok(SSTORE_RESET_GAS_EIP2200);
+    }
+    if original != 0 {
+        if current == 0 {
+            state.sub_refund(SSTORE_CLEARS_SCHEDULE_REFUND_EIP2200);
+        } else if *value == 0 {
+            state.add_refund(SSTORE_CLEARS_SCHEDULE_REFUND_EIP2200);
+        }
+    }
+    if original == *value {
+        if original == 0 {
+            state.add_refund(SSTORE_SET_GAS_EIP2200 - SLOAD_GAS_EIP2200);
+        } else {
+            state.add_refund(SSTORE_RESET_GAS_EIP2200 - SLOAD_GAS_EIP2200);
+        }
+    }
+    Ok(SLOAD_GAS_EIP2200)
+}
+
+/// SstoreGasEip2929 is the net metering of EIP-2200, repriced by Berlin to
+/// charge the access to the slot (EIP-2929). London lowered the refund for
+/// clearing a slot (EIP-3529), so it is given as `clearing_refund`.
+fn sstore_gas_eip2929(
+    state: &mut impl StateDB,
+    address: &Address,
+    key: &U256,
+    value: &U256,
+    gas_left: u64,
+    clearing_refund: u64,
+) -> Result<u64, ReentrancySentry> {
+    if gas_left <= SSTORE_SENTRY_GAS_EIP2200 {
+        return Err(ReentrancySentry);
+    }
+    let mut cost = 0;
+    if !state.slot_in_access_list(address, key).1 {
+        cost = COLD_SLOAD_COST;
+        state.add_slot_to_access_list(address, *key);
+    }
+    let current = state.get_state(address, key);
+    if current == *value {
+        return Ok(cost + WARM_STORAGE_READ_COST);
+    }
+    let original = state.get_committed_state(address, key);
+    if original == current {
+        if original == 0 {
+            return Ok(cost + SSTORE_SET_GAS_EIP2200);
+        }
+        if *value == 0 {
+            state.add_refund(clearing_refund);
+        }
+        return Ok(cost + (SSTORE_RESET_GAS_EIP2200 - COLD_SLOAD_COST));
+    }
+    if original != 0 {
+        if current == 0 {
+            state.sub_refund(clearing_refund);
+        } else if *value == 0 {
+            state.add_refund(clearing_refund);
+        }
+    }
+    if original == *value {
+        if original == 0 {
+            state.add_refund(SSTORE_SET_GAS_EIP2200 - WARM_STORAGE_READ_COST);
+        } else {
+            state.add_refund(SSTORE_RESET_GAS_EIP2200 - COLD_SLOAD_COST - WARM_STORAGE_READ_COST);
+        }
+    }
+    Ok(cost + WARM_STORAGE_READ_COST)
+}
+
+/// MaxRefund returns the largest refund a transaction which used `gas_used`
+/// can get. London lowered it from a half to a fifth (EIP-3529).
+pub fn max_refund(gas_used: u64, fork: Fork) -> u64 {
+    if fork >= Fork::London {
+        gas_used / REFUND_QUOTIENT_EIP3529
+    } else {
+        gas_used / REFUND_QUOTIENT
+    }
+}
+
+/// ToWordSize returns the number of 32-byte words needed to hold `size` bytes.
+pub fn to_word_size(size: u64) -> u64 {
+    if size > u64::MAX - 31 {
+        return u64::MAX / 32 + 1;
+    }
+    (size + 31) / 32
+}
+
+/// MemorySize returns the size the memory must grow to, so that an opcode can
+/// access `size` bytes from `offset`. The size is rounded up to a whole word,
+/// since memory always grows by words. It mirrors geth's `calcMemSize64`.
+///
+/// Accessing 0 bytes does not touch memory at all, whatever the offset, so
+/// the returned size is 0. Otherwise, it fails if the end of the range does
+/// not fit in a u64: such an access could never be paid for anyway.
+pub fn memory_size(offset: U256, size: U256) -> Result<u64, GasUintOverflow> {
+    if size == 0 {
+        return Ok(0);
+    }
+    let offset = u64::try_from(offset).map_err(|_| GasUintOverflow)?;
+    let size = u64::try_from(size).map_err(|_| GasUintOverflow)?;
+    let end = offset.checked_add(size).ok_or(GasUintOverflow)?;
+    to_word_size(end).checked_mul(32).ok_or(GasUintOverflow)
+}
+
+/// GasMeter keeps track of the gas available to an execution. Every opcode
+/// is charged before it runs, and the execution halts as soon as the meter
+/// runs dry.
+pub struct GasMeter {
+    limit: u64,
+    remaining: u64,
+}
+
+impl GasMeter {
+    pub fn new(limit: u64) -> Self {
+        GasMeter {
+            limit,
+            remaining: limit,
+        }
+    }
+
+    /// Get the gas the execution started with
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+
+    /// Get the gas still available
+    pub fn remaining(&self) -> u64 {
+        self.remaining
+    }
+
+    /// Get the gas consumed so far
+    pub fn used(&self) -> u64 {
+        self.limit - self.remaining
+    }
+
+    /// Charge deducts `amount` from the remaining gas. It fails without
+    /// deducting anything if there is not enough gas left.
+    pub fn charge(&mut self, amount: u64) -> Result<(), OutOfGas> {
+        if amount > self.remaining {
+            return Err(OutOfGas {
+                required: amount,
+                remaining: self.remaining,
+            });
+        }
+        self.remaining -= amount;
+        Ok(())
+    }
+
+    /// Consume all the remaining gas. This is what happens when the execution
+    /// fails with an error other than a REVERT.
+    pub fn consume_all(&mut self) {
+        self.remaining = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ethnum::uint;
+
+    use super::*;
+    use crate::state::MemoryStateDB;
+
+    #[test]
+    fn can_get_gas_tier_cost() {
+        assert_eq!(GasTier::Zero.gas(), Some(0));
+        assert_eq!(GasTier::Fastest.gas(), Some(3));
+        assert_eq!(GasTier::Ext.gas(), Some(20));
+        assert_eq!(GasTier::Special.gas(), None);
+        assert_eq!(Opcode::SLOAD.info().gas_tier, GasTier::Special);
+    }
+
+    #[test]
+    fn can_get_exp_gas() {
+        assert_eq!(exp_gas(&uint!("0"), Fork::Cancun), 0);
+        assert_eq!(exp_gas(&uint!("1"), Fork::Cancun), 50);
+        assert_eq!(exp_gas(&uint!("0xff"), Fork::Cancun), 50);
+        assert_eq!(exp_gas(&uint!("0x100"), Fork::Cancun), 100);
+        assert_eq!(exp_gas(&U256::MAX, Fork::Cancun), 32 * 50);
+        assert_eq!(exp_gas(&uint!("0x100"), Fork::TangerineWhistle), 20);
+        assert_eq!(exp_gas(&uint!("0x100"), Fork::SpuriousDragon), 100);
+    }
+
+    #[test]
+    fn can_get_copy_gas() {
+        assert_eq!(copy_gas(&uint!("0")), 0);
+        assert_eq!(copy_gas(&uint!("1")), 3);
+        assert_eq!(copy_gas(&uint!("32")), 3);
+        assert_eq!(copy_gas(&uint!("33")), 6);
+    }
+
+    #[test]
+    fn can_get_keccak256_gas() {
+        assert_eq!(keccak256_gas(&uint!("0")), 0);
+        assert_eq!(keccak256_gas(&uint!("32")), 6);
+        assert_eq!(keccak256_gas(&uint!("33")), 12);
+    }
+
+    const ADDRESS: Address = [0xaa; 20];
+
+    /// SstoreCase runs the SSTOREs writing `values` in turn to a slot holding
+    /// `original`, and returns the total cost and the refund.
+    fn sstore_case(fork: Fork, original: u8, values: &[u8]) -> (u64, u64) {
+        let mut state = MemoryStateDB::new();
+        let key = uint!("1");
+        state.set_state(&ADDRESS, key, U256::from(original));
+        state.finalise();
+        let mut cost = 0;
+        for value in values {
+            let value = U256::from(*value);
+            cost += sstore_gas(&mut state, &ADDRESS, &key, &value, 10_000, fork).unwrap();
+            state.set_state(&ADDRESS, key, value);
+        }
+        (cost, state.get_refund())
+    }
+
+    #[test]
+    fn can_get_sstore_gas() {
+        // The test cases of EIP-2200, also run under London where the slot
+        // starts cold
+        type Case = (u8, &'static [u8], u64, u64, u64, u64);
+        let cases: [Case; 8] = [
+            // (original, values, Istanbul gas, Istanbul refund, London gas, London refund)
+            (0, &[0, 0], 1600, 0, 2300, 0),
+            (0, &[0, 1], 20800, 0, 22200, 0),
+            (0, &[1, 0], 20800, 19200, 22200, 19900),
+            (1, &[0, 0], 5800, 15000, 5100, 4800),
+            (1, &[2, 0], 5800, 15000, 5100, 4800),
+            (1, &[0, 1], 5800, 4200, 5100, 2800),
+            (1, &[1, 1], 1600, 0, 2300, 0),
+            (1, &[0, 2], 5800, 0, 5100, 0),
+        ];
+        for (original, values, istanbul_gas, istanbul_refund, london_gas, london_refund) in cases {
+            assert_eq!(
+                sstore_case(Fork::Istanbul, original, values),
+                (istanbul_gas, istanbul_refund),
+                "Istanbul {original} {values:?}"
+            );
+            assert_eq!(
+                sstore_case(Fork::London, original, values),
+                (london_gas, london_refund),
+                "London {original} {values:?}"
+            );
+        }
+        // Berlin has the clearing refund of Istanbul
+        assert_eq!(sstore_case(Fork::Berlin, 1, &[0]), (5000, 15000));
+        // The original schedule only looks at the current value
+        assert_eq!(sstore_case(Fork::Petersburg, 0, &[1, 0]), (25000, 15000));
+        assert_eq!(
+            sstore_case(Fork::Constantinople, 0, &[1, 0]),
+            (20200, 19800)
+        );
+    }
+
+    #[test]
+    fn cannot_sstore_within_stipend() {
+        let mut state = MemoryStateDB::new();
+        let sstore = |state: &mut MemoryStateDB, gas_left, fork| {
+            sstore_gas(state, &ADDRESS, &uint!("1"), &uint!("1"), gas_left, fork)
+        };
+        assert_eq!(
+            sstore(&mut state, 2300, Fork::Istanbul),
+            Err(ReentrancySentry)
+        );
+        assert_eq!(
+            sstore(&mut state, 2300, Fork::Cancun),
+            Err(ReentrancySentry)
+        );
+        assert_eq!(sstore(&mut state, 2301, Fork::Cancun), Ok(22100));
+        assert_eq!(sstore(&mut state, 2300, Fork::Petersburg), Ok(20000));
+    }
+
+    #[test]
+    fn can_get_sload_gas() {
+        let mut state = MemoryStateDB::new();
+        let key = uint!("1");
+        assert_eq!(sload_gas(&mut state, &ADDRESS, &key, Fork::Istanbul), 0);
+        assert_eq!(sload_gas(&mut state, &ADDRESS, &key, Fork::Berlin), 2100);
+        assert_eq!(sload_gas(&mut state, &ADDRESS, &key, Fork::Berlin), 100);
+    }
+
+    #[test]
+    fn can_get_account_access_gas() {
+        let mut state = MemoryStateDB::new();
+        assert_eq!(account_access_gas(&mut state, &ADDRESS, Fork::Istanbul), 0);
+        assert_eq!(account_access_gas(&mut state, &ADDRESS, Fork::Berlin), 2500);
+        assert_eq!(account_access_gas(&mut state, &ADDRESS, Fork::Berlin), 0);
+        // Accessing a slot warms the account up
+        state.add_slot_to_access_list(&[0xbb; 20], uint!("1"));
+        assert_eq!(account_access_gas(&mut state, &[0xbb; 20], Fork::Berlin), 0);
+    }
+
+    #[test]
+    fn can_get_call_gas() {
+        let mut state = MemoryStateDB::new();
+        // Cold, and creating the callee with the value it receives
+        assert_eq!(
+            call_gas(
+                &mut state,
+                Opcode::CALL,
+                &ADDRESS,
+                &uint!("1"),
+                Fork::Berlin
+            ),
+            2500 + 25000 + 9000
+        );
+        // Warm, and without value the empty callee is not created
+        assert_eq!(
+            call_gas(
+                &mut state,
+                Opcode::CALL,
+                &ADDRESS,
+                &U256::ZERO,
+                Fork::Berlin
+            ),
+            0
+        );
+        state.add_balance(&ADDRESS, uint!("1"));
+        assert_eq!(
+            call_gas(
+                &mut state,
+                Opcode::CALL,
+                &ADDRESS,
+                &uint!("1"),
+                Fork::Berlin
+            ),
+            9000
+        );
+        // Before Spurious Dragon, calling a missing account creates it
+        let bob = [0xbb; 20];
+        assert_eq!(
+            call_gas(&mut state, Opcode::CALL, &bob, &U256::ZERO, Fork::Homestead),
+            25000
+        );
+        // CALLCODE never creates the callee, the others never transfer value
+        assert_eq!(
+            call_gas(
+                &mut state,
+                Opcode::CALLCODE,
+                &bob,
+                &uint!("1"),
+                Fork::Berlin
+            ),
+            2500 + 9000
+        );
+        for (op, address) in [
+            (Opcode::DELEGATECALL, [0xcc; 20]),
+            (Opcode::STATICCALL, [0xdd; 20]),
+        ] {
+            assert_eq!(
+                call_gas(&mut state, op, &address, &uint!("1"), Fork::Berlin),
+                2500
+            );
+        }
+    }
+
+    #[test]
+    fn can_cap_refund() {
+        assert_eq!(max_refund(100, Fork::Berlin), 50);
+        assert_eq!(max_refund(100, Fork::London), 20);
+    }
+
+    #[test]
+    fn can_get_word_size() {
+        assert_eq!(to_word_size(0), 0);
+        assert_eq!(to_word_size(1), 1);
+        assert_eq!(to_word_size(32), 1);
+        assert_eq!(to_word_size(33), 2);
+        assert_eq!(to_word_size(u64::MAX), u64::MAX / 32 + 1);
+    }
+
+    #[test]
+    fn can_get_memory_size() {
+        assert_eq!(memory_size(uint!("0"), uint!("0")), Ok(0));
+        // The offset is ignored when nothing is accessed
+        assert_eq!(memory_size(U256::MAX, uint!("0")), Ok(0));
+        assert_eq!(memory_size(uint!("0"), uint!("1")), Ok(32));
+        assert_eq!(memory_size(uint!("0"), uint!("32")), Ok(32));
+        assert_eq!(memory_size(uint!("1"), uint!("32")), Ok(64));
+        assert_eq!(memory_size(uint!("64"), uint!("32")), Ok(96));
+    }
+
+    #[test]
+    fn cannot_get_memory_size_overflowing_u64() {
+        assert_eq!(memory_size(U256::MAX, uint!("1")), Err(GasUintOverflow));
+        assert_eq!(memory_size(uint!("0"), U256::MAX), Err(GasUintOverflow));
+        assert_eq!(
+            memory_size(U256::from(u64::MAX), uint!("1")),
+            Err(GasUintOverflow)
+        );
+        assert_eq!(
+            memory_size(U256::from(u64::MAX - 1), uint!("1")),
+            Err(GasUintOverflow)
+        );
+    }
+
+    #[test]
+    fn can_charge() {
+        let mut meter = GasMeter::new(10);
+        meter.charge(3).unwrap();
+        assert_eq!(meter.remaining(), 7);
+        assert_eq!(meter.used(), 3);
+        meter.charge(7).unwrap();
+        assert_eq!(meter.remaining(), 0);
+        assert_eq!(meter.used(), 10);
+    }
+
+    #[test]
+    fn cannot_charge_more_than_remaining() {
+        let mut meter = GasMeter::new(10);
+        meter.charge(3).unwrap();
+        assert_eq!(
+            meter.charge(8),
+            Err(OutOfGas {
+                required: 8,
+                remaining: 7
+            })
+        );
+        assert_eq!(meter.remaining(), 7);
+    }
+
+    #[test]
+    fn can_consume_all() {
+        let mut meter = GasMeter::new(10);
+        meter.consume_all();
+        assert_eq!(meter.remaining(), 0);
+        assert_eq!(meter.used(), 10);
+        assert_eq!(meter.limit(), 10);
+    }
+}
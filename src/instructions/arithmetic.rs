@@ -0,0 +1,344 @@
+//! Arithmetic instructions, i.e. the 0x0 range of opcodes from ADD to
+//! SIGNEXTEND.
+//!
+//! All the words on the stack are unsigned 256-bit integers. The signed
+//! instructions (SDIV, SMOD and SIGNEXTEND) reinterpret them as two's
+//! complement integers. Every operation wraps around on overflow, modulo
+//! 2^256.
+
+use ethnum::U256;
+
+use crate::{interpreter::ExecutionError, stack::Stack};
+
+/// ADD pops `a` and `b` and pushes `a + b`.
+pub fn add(stack: &mut Stack) -> Result<(), ExecutionError> {
+    let a = stack.try_pop()?;
+    let b = stack.try_pop()?;
+    stack.push(a.wrapping_add(b));
+    Ok(())
+}
+
+/// MUL pops `a` and `b` and pushes `a * b`.
+pub fn mul(stack: &mut Stack) -> Result<(), ExecutionError> {
+    let a = stack.try_pop()?;
+    let b = stack.try_pop()?;
+    stack.push(a.wrapping_mul(b));
+    Ok(())
+}
+
+/// SUB pops `a` and `b` and pushes `a - b`.
+pub fn sub(stack: &mut Stack) -> Result<(), ExecutionError> {
+    let a = stack.try_pop()?;
+    let b = stack.try_pop()?;
+    stack.push(a.wrapping_sub(b));
+    Ok(())
+}
+
+/// DIV pops `a` and `b` and pushes `a / b`, or 0 if `b` is 0.
+pub fn div(stack: &mut Stack) -> Result<(), ExecutionError> {
+    let a = stack.try_pop()?;
+    let b = stack.try_pop()?;
+    stack.push(a.checked_div(b).unwrap_or(U256::ZERO));
+    Ok(())
+}
+
+/// SDIV pops `a` and `b` and pushes `a / b`, both treated as signed, or 0 if
+/// `b` is 0.
+///
+/// The result is rounded towards zero. The only overflowing case,
+/// `-2^255 / -1`, wraps around to `-2^255`.
+pub fn sdiv(stack: &mut Stack) -> Result<(), ExecutionError> {
+    let a = stack.try_pop()?.as_i256();
+    let b = stack.try_pop()?.as_i256();
+    if b == 0 {
+        stack.push(U256::ZERO);
+    } else {
+        stack.push(a.wrapping_div(b).as_u256());
+    }
+    Ok(())
+}
+
+/// MOD pops `a` and `b` and pushes `a % b`, or 0 if `b` is 0.
+pub fn modulo(stack: &mut Stack) -> Result<(), ExecutionError> {
+    let a = stack.try_pop()?;
+    let b = stack.try_pop()?;
+    stack.push(a.checked_rem(b).unwrap_or(U256::ZERO));
+    Ok(())
+}
+
+/// SMOD pops `a` and `b` and pushes `a % b`, both treated as signed, or 0 if
+/// `b` is 0.
+///
+/// The result takes the sign of `a`.
+pub fn smod(stack: &mut Stack) -> Result<(), ExecutionError> {
+    let a = stack.try_pop()?.as_i256();
+    let b = stack.try_pop()?.as_i256();
+    if b == 0 {
+        stack.push(U256::ZERO);
+    } else {
+        stack.push(a.wrapping_rem(b).as_u256());
+    }
+    Ok(())
+}
+
+/// ADDMOD pops `a`, `b` and `n` and pushes `(a + b) % n`, or 0 if `n` is 0.
+///
+/// Unlike ADD followed by MOD, the intermediate sum is not wrapped around.
+pub fn addmod(stack: &mut Stack) -> Result<(), ExecutionError> {
+    let a = stack.try_pop()?;
+    let b = stack.try_pop()?;
+    let n = stack.try_pop()?;
+    if n == 0 {
+        stack.push(U256::ZERO);
+    } else {
+        stack.push(add_mod(a % n, b % n, n));
+    }
+    Ok(())
+}
+
+/// MULMOD pops `a`, `b` and `n` and pushes `(a * b) % n`, or 0 if `n` is 0.
+///
+/// Unlike MUL followed by MOD, the intermediate product is not wrapped around.
+pub fn mulmod(stack: &mut Stack) -> Result<(), ExecutionError> {
+    let a = stack.try_pop()?;
+    let b = stack.try_pop()?;
+    let n = stack.try_pop()?;
+    if n == 0 {
+        stack.push(U256::ZERO);
+    } else {
+        stack.push(mul_mod(a % n, b % n, n));
+    }
+    Ok(())
+}
+
+/// EXP pops `base` and `exponent` and pushes `base ** exponent`.
+pub fn exp(stack: &mut Stack) -> Result<(), ExecutionError> {
+    let base = stack.try_pop()?;
+    let exponent = stack.try_pop()?;
+    stack.push(pow(base, exponent));
+    Ok(())
+}
+
+/// SIGNEXTEND pops `b` and `x` and pushes `x` sign-extended from its `b`th
+/// byte, counting from the least significant byte (starting from 0).
+///
+/// E.g. `SIGNEXTEND(0, 0xff)` is `-1`, because `0xff` is `-1` as an 8-bit
+/// integer. If `b` is 31 or more, `x` is already a full 256-bit word and is
+/// pushed unchanged.
+pub fn signextend(stack: &mut Stack) -> Result<(), ExecutionError> {
+    let b = stack.try_pop()?;
+    let x = stack.try_pop()?;
+    if b >= 31 {
+        stack.push(x);
+        return Ok(());
+    }
+
+    let sign_bit = b.as_u32() * 8 + 7;
+    let mask = (U256::ONE << sign_bit) - 1;
+    if x & (U256::ONE << sign_bit) == 0 {
+        stack.push(x & mask);
+    } else {
+        stack.push(x | !mask);
+    }
+    Ok(())
+}
+
+/// Compute `(a + b) % n` without losing the carry of `a + b`.
+///
+/// Both `a` and `b` must already be reduced modulo `n`, so the exact sum is
+/// less than `2n` and at most one subtraction of `n` is needed.
+fn add_mod(a: U256, b: U256, n: U256) -> U256 {
+    let (sum, carry) = a.overflowing_add(b);
+    if carry || sum >= n {
+        // If the addition carried, the exact sum is `sum + 2^256`, and
+        // wrapping around when subtracting `n` takes care of the missing bit.
+        sum.wrapping_sub(n)
+    } else {
+        sum
+    }
+}
+
+/// Compute `(a * b) % n` as if the product was a 512-bit intermediate.
+///
+/// This is the schoolbook double-and-add algorithm: walking the bits of `b`
+/// from the most significant, the accumulator is doubled and `a` is added for
+/// every set bit, reducing modulo `n` at each step so it never overflows.
+///
+/// Both `a` and `b` must already be reduced modulo `n`.
+fn mul_mod(a: U256, b: U256, n: U256) -> U256 {
+    let mut result = U256::ZERO;
+    for i in (0..256 - b.leading_zeros()).rev() {
+        result = add_mod(result, result, n);
+        if b & (U256::ONE << i) != 0 {
+            result = add_mod(result, a, n);
+        }
+    }
+    result
+}
+
+/// Compute `base ** exponent` modulo 2^256 by square-and-multiply.
+fn pow(mut base: U256, mut exponent: U256) -> U256 {
+    let mut result = U256::ONE;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = result.wrapping_mul(base);
+        }
+        base = base.wrapping_mul(base);
+        exponent >>= 1;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use ethnum::{uint, U256};
+
+    use super::*;
+    use crate::{
+        instructions::tests::{run, MAX, MIN_SIGNED},
+        stack::StackError,
+    };
+
+    #[test]
+    fn can_add() {
+        assert_eq!(run(add, &[uint!("1"), uint!("2")]), uint!("3"));
+        assert_eq!(run(add, &[MAX, uint!("1")]), U256::ZERO);
+        assert_eq!(run(add, &[MAX, MAX]), MAX - 1);
+    }
+
+    #[test]
+    fn can_mul() {
+        assert_eq!(run(mul, &[uint!("6"), uint!("7")]), uint!("42"));
+        assert_eq!(run(mul, &[MAX, uint!("2")]), MAX - 1);
+        assert_eq!(run(mul, &[MAX, MAX]), U256::ONE);
+    }
+
+    #[test]
+    fn can_sub() {
+        assert_eq!(run(sub, &[uint!("3"), uint!("2")]), uint!("1"));
+        assert_eq!(run(sub, &[U256::ZERO, uint!("1")]), MAX);
+    }
+
+    #[test]
+    fn can_div() {
+        assert_eq!(run(div, &[uint!("10"), uint!("3")]), uint!("3"));
+        assert_eq!(run(div, &[uint!("10"), U256::ZERO]), U256::ZERO);
+        assert_eq!(run(div, &[MAX, MAX]), U256::ONE);
+    }
+
+    #[test]
+    fn can_sdiv() {
+        // -10 / 3 = -3 (rounded towards zero)
+        assert_eq!(
+            run(sdiv, &[uint!("10").wrapping_neg(), uint!("3")]),
+            uint!("3").wrapping_neg()
+        );
+        // -1 / -1 = 1
+        assert_eq!(run(sdiv, &[MAX, MAX]), U256::ONE);
+        assert_eq!(run(sdiv, &[uint!("10"), U256::ZERO]), U256::ZERO);
+    }
+
+    #[test]
+    fn can_sdiv_min_by_minus_one() {
+        // -2^255 / -1 overflows and wraps around to -2^255
+        assert_eq!(run(sdiv, &[MIN_SIGNED, MAX]), MIN_SIGNED);
+    }
+
+    #[test]
+    fn can_mod() {
+        assert_eq!(run(modulo, &[uint!("10"), uint!("3")]), uint!("1"));
+        assert_eq!(run(modulo, &[uint!("10"), U256::ZERO]), U256::ZERO);
+    }
+
+    #[test]
+    fn can_smod() {
+        // -10 % 3 = -1, the result takes the sign of the dividend
+        assert_eq!(run(smod, &[uint!("10").wrapping_neg(), uint!("3")]), MAX);
+        // 10 % -3 = 1
+        assert_eq!(
+            run(smod, &[uint!("10"), uint!("3").wrapping_neg()]),
+            U256::ONE
+        );
+        assert_eq!(run(smod, &[MIN_SIGNED, MAX]), U256::ZERO);
+        assert_eq!(run(smod, &[uint!("10"), U256::ZERO]), U256::ZERO);
+    }
+
+    #[test]
+    fn can_addmod() {
+        assert_eq!(
+            run(addmod, &[uint!("10"), uint!("10"), uint!("8")]),
+            uint!("4")
+        );
+        assert_eq!(
+            run(addmod, &[uint!("10"), uint!("10"), U256::ZERO]),
+            U256::ZERO
+        );
+    }
+
+    #[test]
+    fn can_addmod_without_wrapping_intermediate() {
+        // (2^256 + 1) % 3 = 2, whereas a wrapped intermediate would give 1
+        assert_eq!(run(addmod, &[MAX, uint!("2"), uint!("3")]), uint!("2"));
+        assert_eq!(run(addmod, &[MAX, MAX, MAX - 1]), uint!("2"));
+    }
+
+    #[test]
+    fn can_mulmod() {
+        assert_eq!(
+            run(mulmod, &[uint!("10"), uint!("10"), uint!("8")]),
+            uint!("4")
+        );
+        assert_eq!(
+            run(mulmod, &[uint!("10"), uint!("10"), U256::ZERO]),
+            U256::ZERO
+        );
+    }
+
+    #[test]
+    fn can_mulmod_without_wrapping_intermediate() {
+        // (2^256 - 1)^2 % 12 = 9, since 2^256 - 1 = 3 (mod 12)
+        assert_eq!(run(mulmod, &[MAX, MAX, uint!("12")]), uint!("9"));
+        // (2^256 - 1)^2 % (2^256 - 2) = 1, since 2^256 - 1 = 1 (mod 2^256 - 2)
+        assert_eq!(run(mulmod, &[MAX, MAX, MAX - 1]), U256::ONE);
+    }
+
+    #[test]
+    fn can_exp() {
+        assert_eq!(run(exp, &[uint!("2"), uint!("10")]), uint!("1024"));
+        assert_eq!(run(exp, &[uint!("0"), uint!("0")]), U256::ONE);
+        assert_eq!(run(exp, &[uint!("2"), uint!("255")]), MIN_SIGNED);
+        assert_eq!(run(exp, &[uint!("2"), uint!("256")]), U256::ZERO);
+        // (-1)^odd = -1
+        assert_eq!(run(exp, &[MAX, MAX]), MAX);
+    }
+
+    #[test]
+    fn can_signextend() {
+        assert_eq!(run(signextend, &[U256::ZERO, uint!("0xff")]), MAX);
+        assert_eq!(run(signextend, &[U256::ZERO, uint!("0x7f")]), uint!("0x7f"));
+        assert_eq!(
+            run(signextend, &[U256::ONE, uint!("0xff7f")]),
+            uint!("0xff7f") | (MAX << 16)
+        );
+        // Higher bytes are cleared when the sign bit is not set
+        assert_eq!(
+            run(signextend, &[U256::ZERO, uint!("0x1234")]),
+            uint!("0x34")
+        );
+        assert_eq!(
+            run(signextend, &[uint!("31"), uint!("0x80")]),
+            uint!("0x80")
+        );
+        assert_eq!(run(signextend, &[MAX, uint!("0xff")]), uint!("0xff"));
+    }
+
+    #[test]
+    fn cannot_add_with_one_element() {
+        let mut stack = Stack::new();
+        stack.push(U256::ONE);
+        assert!(matches!(
+            add(&mut stack),
+            Err(ExecutionError::Stack(StackError::StackUnderflow { .. }))
+        ));
+    }
+}
@@ -0,0 +1,255 @@
+//! Comparison, bitwise and shift instructions, i.e. the 0x10 range of opcodes
+//! from LT to SAR.
+//!
+//! Comparisons push 1 when they hold and 0 otherwise. The signed comparisons
+//! (SLT and SGT) and the arithmetic shift (SAR) reinterpret the words as two's
+//! complement integers.
+
+use ethnum::U256;
+
+use crate::{interpreter::ExecutionError, stack::Stack};
+
+/// Convert a boolean into the word pushed by comparisons.
+fn from_bool(value: bool) -> U256 {
+    if value {
+        U256::ONE
+    } else {
+        U256::ZERO
+    }
+}
+
+/// LT pops `a` and `b` and pushes `a < b`.
+pub fn lt(stack: &mut Stack) -> Result<(), ExecutionError> {
+    let a = stack.try_pop()?;
+    let b = stack.try_pop()?;
+    stack.push(from_bool(a < b));
+    Ok(())
+}
+
+/// GT pops `a` and `b` and pushes `a > b`.
+pub fn gt(stack: &mut Stack) -> Result<(), ExecutionError> {
+    let a = stack.try_pop()?;
+    let b = stack.try_pop()?;
+    stack.push(from_bool(a > b));
+    Ok(())
+}
+
+/// SLT pops `a` and `b` and pushes `a < b`, both treated as signed.
+pub fn slt(stack: &mut Stack) -> Result<(), ExecutionError> {
+    let a = stack.try_pop()?.as_i256();
+    let b = stack.try_pop()?.as_i256();
+    stack.push(from_bool(a < b));
+    Ok(())
+}
+
+/// SGT pops `a` and `b` and pushes `a > b`, both treated as signed.
+pub fn sgt(stack: &mut Stack) -> Result<(), ExecutionError> {
+    let a = stack.try_pop()?.as_i256();
+    let b = stack.try_pop()?.as_i256();
+    stack.push(from_bool(a > b));
+    Ok(())
+}
+
+/// EQ pops `a` and `b` and pushes `a == b`.
+pub fn eq(stack: &mut Stack) -> Result<(), ExecutionError> {
+    let a = stack.try_pop()?;
+    let b = stack.try_pop()?;
+    stack.push(from_bool(a == b));
+    Ok(())
+}
+
+/// ISZERO pops `a` and pushes `a == 0`.
+pub fn iszero(stack: &mut Stack) -> Result<(), ExecutionError> {
+    let a = stack.try_pop()?;
+    stack.push(from_bool(a == 0));
+    Ok(())
+}
+
+/// AND pops `a` and `b` and pushes `a & b`.
+pub fn and(stack: &mut Stack) -> Result<(), ExecutionError> {
+    let a = stack.try_pop()?;
+    let b = stack.try_pop()?;
+    stack.push(a & b);
+    Ok(())
+}
+
+/// OR pops `a` and `b` and pushes `a | b`.
+pub fn or(stack: &mut Stack) -> Result<(), ExecutionError> {
+    let a = stack.try_pop()?;
+    let b = stack.try_pop()?;
+    stack.push(a | b);
+    Ok(())
+}
+
+/// XOR pops `a` and `b` and pushes `a ^ b`.
+pub fn xor(stack: &mut Stack) -> Result<(), ExecutionError> {
+    let a = stack.try_pop()?;
+    let b = stack.try_pop()?;
+    stack.push(a ^ b);
+    Ok(())
+}
+
+/// NOT pops `a` and pushes `!a`.
+pub fn not(stack: &mut Stack) -> Result<(), ExecutionError> {
+    let a = stack.try_pop()?;
+    stack.push(!a);
+    Ok(())
+}
+
+/// BYTE pops `i` and `x` and pushes the `i`th byte of `x`, counting from the
+/// most significant byte (starting from 0), or 0 if `i` is 32 or more.
+pub fn byte(stack: &mut Stack) -> Result<(), ExecutionError> {
+    let i = stack.try_pop()?;
+    let x = stack.try_pop()?;
+    if i < 32 {
+        stack.push(U256::from(x.to_be_bytes()[i.as_usize()]));
+    } else {
+        stack.push(U256::ZERO);
+    }
+    Ok(())
+}
+
+/// SHL pops `shift` and `value` and pushes `value << shift`, or 0 if `shift`
+/// is 256 or more.
+pub fn shl(stack: &mut Stack) -> Result<(), ExecutionError> {
+    let shift = stack.try_pop()?;
+    let value = stack.try_pop()?;
+    if shift < 256 {
+        stack.push(value << shift.as_u32());
+    } else {
+        stack.push(U256::ZERO);
+    }
+    Ok(())
+}
+
+/// SHR pops `shift` and `value` and pushes `value >> shift` filling with
+/// zeroes, or 0 if `shift` is 256 or more.
+pub fn shr(stack: &mut Stack) -> Result<(), ExecutionError> {
+    let shift = stack.try_pop()?;
+    let value = stack.try_pop()?;
+    if shift < 256 {
+        stack.push(value >> shift.as_u32());
+    } else {
+        stack.push(U256::ZERO);
+    }
+    Ok(())
+}
+
+/// SAR pops `shift` and `value` and pushes `value >> shift` filling with the
+/// sign bit of `value`.
+///
+/// If `shift` is 256 or more, every bit is the sign bit, so the result is 0
+/// for a positive `value` and -1 for a negative one.
+pub fn sar(stack: &mut Stack) -> Result<(), ExecutionError> {
+    let shift = stack.try_pop()?;
+    let value = stack.try_pop()?.as_i256();
+    if shift < 256 {
+        stack.push((value >> shift.as_u32()).as_u256());
+    } else if value < 0 {
+        stack.push(U256::MAX);
+    } else {
+        stack.push(U256::ZERO);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use ethnum::{uint, U256};
+
+    use super::*;
+    use crate::instructions::tests::{run, MAX, MIN_SIGNED};
+
+    #[test]
+    fn can_compare_unsigned() {
+        assert_eq!(run(lt, &[uint!("1"), uint!("2")]), U256::ONE);
+        assert_eq!(run(lt, &[uint!("2"), uint!("2")]), U256::ZERO);
+        assert_eq!(run(lt, &[U256::ONE, MAX]), U256::ONE);
+        assert_eq!(run(gt, &[uint!("2"), uint!("1")]), U256::ONE);
+        assert_eq!(run(gt, &[uint!("2"), uint!("2")]), U256::ZERO);
+        assert_eq!(run(gt, &[U256::ONE, MAX]), U256::ZERO);
+    }
+
+    #[test]
+    fn can_compare_signed() {
+        // -1 < 1
+        assert_eq!(run(slt, &[MAX, U256::ONE]), U256::ONE);
+        assert_eq!(run(slt, &[U256::ONE, MAX]), U256::ZERO);
+        assert_eq!(run(slt, &[MIN_SIGNED, MAX]), U256::ONE);
+        // 1 > -1
+        assert_eq!(run(sgt, &[U256::ONE, MAX]), U256::ONE);
+        assert_eq!(run(sgt, &[MAX, U256::ONE]), U256::ZERO);
+        assert_eq!(run(sgt, &[MAX, MAX]), U256::ZERO);
+    }
+
+    #[test]
+    fn can_eq() {
+        assert_eq!(run(eq, &[MAX, MAX]), U256::ONE);
+        assert_eq!(run(eq, &[MAX, U256::ONE]), U256::ZERO);
+    }
+
+    #[test]
+    fn can_iszero() {
+        assert_eq!(run(iszero, &[U256::ZERO]), U256::ONE);
+        assert_eq!(run(iszero, &[MAX]), U256::ZERO);
+    }
+
+    #[test]
+    fn can_do_bitwise_logic() {
+        assert_eq!(
+            run(and, &[uint!("0b1100"), uint!("0b1010")]),
+            uint!("0b1000")
+        );
+        assert_eq!(
+            run(or, &[uint!("0b1100"), uint!("0b1010")]),
+            uint!("0b1110")
+        );
+        assert_eq!(
+            run(xor, &[uint!("0b1100"), uint!("0b1010")]),
+            uint!("0b0110")
+        );
+        assert_eq!(run(not, &[U256::ZERO]), MAX);
+    }
+
+    #[test]
+    fn can_byte() {
+        let x = uint!("0x0102030405060708091011121314151617181920212223242526272829303132");
+        assert_eq!(run(byte, &[U256::ZERO, x]), uint!("0x01"));
+        assert_eq!(run(byte, &[uint!("1"), x]), uint!("0x02"));
+        assert_eq!(run(byte, &[uint!("31"), x]), uint!("0x32"));
+        assert_eq!(run(byte, &[uint!("32"), x]), U256::ZERO);
+        assert_eq!(run(byte, &[MAX, x]), U256::ZERO);
+    }
+
+    #[test]
+    fn can_shl() {
+        assert_eq!(run(shl, &[uint!("1"), U256::ONE]), uint!("2"));
+        assert_eq!(run(shl, &[uint!("255"), U256::ONE]), MIN_SIGNED);
+        assert_eq!(run(shl, &[uint!("1"), MAX]), MAX - 1);
+        assert_eq!(run(shl, &[uint!("256"), U256::ONE]), U256::ZERO);
+        assert_eq!(run(shl, &[MAX, MAX]), U256::ZERO);
+    }
+
+    #[test]
+    fn can_shr() {
+        assert_eq!(run(shr, &[uint!("1"), uint!("2")]), U256::ONE);
+        assert_eq!(run(shr, &[uint!("255"), MIN_SIGNED]), U256::ONE);
+        assert_eq!(run(shr, &[uint!("1"), MAX]), MAX >> 1);
+        assert_eq!(run(shr, &[uint!("256"), MAX]), U256::ZERO);
+    }
+
+    #[test]
+    fn can_sar() {
+        assert_eq!(run(sar, &[uint!("1"), uint!("2")]), U256::ONE);
+        // The sign bit is copied into the vacated bits
+        assert_eq!(
+            run(sar, &[uint!("1"), MIN_SIGNED]),
+            MIN_SIGNED | (MIN_SIGNED >> 1)
+        );
+        assert_eq!(run(sar, &[uint!("255"), MIN_SIGNED]), MAX);
+        assert_eq!(run(sar, &[uint!("256"), MIN_SIGNED]), MAX);
+        assert_eq!(run(sar, &[MAX, MIN_SIGNED]), MAX);
+        assert_eq!(run(sar, &[uint!("256"), MAX >> 1]), U256::ZERO);
+        assert_eq!(run(sar, &[uint!("254"), MAX >> 1]), U256::ONE);
+    }
+}
@@ -0,0 +1,121 @@
+//! Control flow instructions: JUMP, JUMPI, PC and GAS.
+//!
+//! Unlike other handlers, the jumps do not move the program counter
+//! themselves: they return the destination, and the interpreter moves there.
+//! STOP and JUMPDEST have no handler, since they do not touch the machine
+//! state: the interpreter handles them directly.
+
+use ethnum::U256;
+
+use crate::{
+    analysis::{valid_jumpdest, Bitvec},
+    gas::GasMeter,
+    interpreter::ExecutionError,
+    stack::Stack,
+};
+
+/// JUMP pops `dest` and returns it as the next program counter, failing if
+/// it is not a valid JUMPDEST of `code`. `analysis` must be the bitmap of
+/// `code`.
+pub fn jump(stack: &mut Stack, code: &[u8], analysis: &Bitvec) -> Result<usize, ExecutionError> {
+    let dest = stack.try_pop()?;
+    if !valid_jumpdest(code, analysis, dest) {
+        return Err(ExecutionError::InvalidJump { dest });
+    }
+    Ok(dest.as_usize())
+}
+
+/// JUMPI pops `dest` and `cond`, and returns `dest` as the next program
+/// counter if `cond` is not 0. It fails if it jumps to a destination that is
+/// not a valid JUMPDEST of `code`, but the destination is not checked when
+/// there is no jump. `analysis` must be the bitmap of `code`.
+pub fn jumpi(
+    stack: &mut Stack,
+    code: &[u8],
+    analysis: &Bitvec,
+) -> Result<Option<usize>, ExecutionError> {
+    let dest = stack.try_pop()?;
+    let cond = stack.try_pop()?;
+    if cond == 0 {
+        return Ok(None);
+    }
+    if !valid_jumpdest(code, analysis, dest) {
+        return Err(ExecutionError::InvalidJump { dest });
+    }
+    Ok(Some(dest.as_usize()))
+}
+
+/// PC pushes the program counter of the PC opcode itself.
+pub fn pc(stack: &mut Stack, pc: usize) -> Result<(), ExecutionError> {
+    stack.push(U256::from(pc as u64));
+    Ok(())
+}
+
+/// GAS pushes the remaining gas, after paying for the GAS opcode itself.
+pub fn gas(stack: &mut Stack, gas: &GasMeter) -> Result<(), ExecutionError> {
+    stack.push(U256::from(gas.remaining()));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use ethnum::uint;
+
+    use super::*;
+    use crate::{analysis::code_bitmap, instructions::tests::stack_of};
+
+    // PUSH1 0x5b, JUMPDEST, STOP
+    const CODE: [u8; 4] = [0x60, 0x5b, 0x5b, 0x00];
+
+    #[test]
+    fn can_jump() {
+        let mut stack = stack_of(&[uint!("2")]);
+        assert_eq!(jump(&mut stack, &CODE, &code_bitmap(&CODE)).unwrap(), 2);
+        assert_eq!(stack.len(), 0);
+    }
+
+    #[test]
+    fn cannot_jump_to_invalid_destination() {
+        for dest in [uint!("0"), uint!("1"), uint!("3"), uint!("4"), U256::MAX] {
+            let mut stack = stack_of(&[dest]);
+            assert!(matches!(
+                jump(&mut stack, &CODE, &code_bitmap(&CODE)),
+                Err(ExecutionError::InvalidJump { dest: d }) if d == dest
+            ));
+        }
+    }
+
+    #[test]
+    fn can_jumpi() {
+        let mut stack = stack_of(&[uint!("2"), uint!("1")]);
+        assert_eq!(
+            jumpi(&mut stack, &CODE, &code_bitmap(&CODE)).unwrap(),
+            Some(2)
+        );
+        assert_eq!(stack.len(), 0);
+
+        let mut stack = stack_of(&[uint!("2"), uint!("0")]);
+        assert_eq!(jumpi(&mut stack, &CODE, &code_bitmap(&CODE)).unwrap(), None);
+        assert_eq!(stack.len(), 0);
+    }
+
+    #[test]
+    fn can_jumpi_to_invalid_destination_without_jumping() {
+        let mut stack = stack_of(&[uint!("1"), uint!("0")]);
+        assert_eq!(jumpi(&mut stack, &CODE, &code_bitmap(&CODE)).unwrap(), None);
+
+        let mut stack = stack_of(&[uint!("1"), uint!("1")]);
+        assert!(matches!(
+            jumpi(&mut stack, &CODE, &code_bitmap(&CODE)),
+            Err(ExecutionError::InvalidJump { .. })
+        ));
+    }
+
+    #[test]
+    fn can_push_pc_and_gas() {
+        let mut stack = Stack::new();
+        pc(&mut stack, 42).unwrap();
+        gas(&mut stack, &GasMeter::new(1000)).unwrap();
+        assert_eq!(stack.data(), &[uint!("42"), uint!("1000")]);
+    }
+}
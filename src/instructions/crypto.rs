@@ -0,0 +1,72 @@
+//! Cryptographic instructions: KECCAK256.
+//!
+//! The interpreter expands the memory before running KECCAK256, so that the
+//! hashed range is always in bounds.
+
+use ethnum::U256;
+
+use crate::{
+    crypto::{Hasher, PreimageCache},
+    interpreter::ExecutionError,
+    memory::Memory,
+    stack::Stack,
+};
+
+/// KECCAK256 pops `offset` and `size` and pushes the Keccak-256 hash of that
+/// memory range. The preimage is recorded in `preimages`, if given.
+pub fn keccak256(
+    stack: &mut Stack,
+    memory: &Memory,
+    hasher: &dyn Hasher,
+    preimages: Option<&mut PreimageCache>,
+) -> Result<(), ExecutionError> {
+    let offset = stack.try_pop()?;
+    let size = stack.try_pop()?;
+    let data = memory.try_get_u256(offset, size)?;
+    let hash = U256::from_be_bytes(hasher.keccak256(&data));
+    if let Some(preimages) = preimages {
+        preimages.insert(hash, &data);
+    }
+    stack.push(hash);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use ethnum::uint;
+
+    use super::*;
+    use crate::{crypto::DefaultHasher, instructions::tests::stack_of};
+
+    #[test]
+    fn can_hash_memory() {
+        let mut memory = Memory::new();
+        memory.resize(32);
+        let mut preimages = PreimageCache::new();
+
+        let mut stack = stack_of(&[uint!("0"), uint!("32")]);
+        keccak256(
+            &mut stack,
+            &memory,
+            &DefaultHasher::default(),
+            Some(&mut preimages),
+        )
+        .unwrap();
+        let hash = uint!("0x290decd9548b62a8d60345a988386fc84ba6bc95484008f6362f93160ef3e563");
+        assert_eq!(stack.data(), &[hash]);
+        assert_eq!(preimages.get(&hash), Some(&[0u8; 32][..]));
+    }
+
+    #[test]
+    fn can_hash_nothing() {
+        // The offset is ignored when nothing is hashed
+        let mut stack = stack_of(&[U256::MAX, uint!("0")]);
+        keccak256(&mut stack, &Memory::new(), &DefaultHasher::default(), None).unwrap();
+        assert_eq!(
+            stack.data(),
+            &[uint!(
+                "0xc5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+            )]
+        );
+    }
+}
@@ -0,0 +1,235 @@
+//! Environment instructions: ADDRESS, BALANCE, CALLER, CALLVALUE,
+//! CALLDATALOAD, CALLDATASIZE, CALLDATACOPY, CODESIZE, CODECOPY, EXTCODESIZE,
+//! EXTCODECOPY and EXTCODEHASH.
+//!
+//! Reading past the end of the calldata or the code yields zeros, as if they
+//! were padded with zeros forever.
+
+use ethnum::U256;
+
+use crate::{
+    contract::{address_to_word, word_to_address, Contract},
+    interpreter::ExecutionError,
+    memory::Memory,
+    stack::Stack,
+    state::StateDB,
+};
+
+/// GetData returns `size` bytes of `data` from `start`, padded with zeros past
+/// the end of `data`. It is geth's `getData`.
+fn get_data(data: &[u8], start: U256, size: usize) -> Vec<u8> {
+    let start = usize::try_from(start).unwrap_or(usize::MAX).min(data.len());
+    let end = start.saturating_add(size).min(data.len());
+    let mut bytes = data[start..end].to_vec();
+    bytes.resize(size, 0);
+    bytes
+}
+
+/// ADDRESS pushes the address of the account whose code runs.
+pub fn address(stack: &mut Stack, contract: &Contract) -> Result<(), ExecutionError> {
+    stack.push(address_to_word(&contract.address));
+    Ok(())
+}
+
+/// BALANCE pops `address` and pushes the balance of that account.
+pub fn balance(stack: &mut Stack, state: &impl StateDB) -> Result<(), ExecutionError> {
+    let address = word_to_address(&stack.try_pop()?);
+    stack.push(state.get_balance(&address));
+    Ok(())
+}
+
+/// CALLER pushes the address of the account which made the call.
+pub fn caller(stack: &mut Stack, contract: &Contract) -> Result<(), ExecutionError> {
+    stack.push(address_to_word(&contract.caller));
+    Ok(())
+}
+
+/// CALLVALUE pushes the wei sent along with the call.
+pub fn callvalue(stack: &mut Stack, contract: &Contract) -> Result<(), ExecutionError> {
+    stack.push(contract.value);
+    Ok(())
+}
+
+/// CALLDATALOAD pops `offset` and pushes the word of calldata starting at
+/// `offset`.
+pub fn calldataload(stack: &mut Stack, contract: &Contract) -> Result<(), ExecutionError> {
+    let offset = stack.try_pop()?;
+    let word = get_data(&contract.input, offset, 32);
+    stack.push(U256::from_be_bytes(word.try_into().unwrap()));
+    Ok(())
+}
+
+/// CALLDATASIZE pushes the size of the calldata in bytes.
+pub fn calldatasize(stack: &mut Stack, contract: &Contract) -> Result<(), ExecutionError> {
+    stack.push(U256::from(contract.input.len() as u64));
+    Ok(())
+}
+
+/// CALLDATACOPY pops `dst`, `offset` and `len` and copies `len` bytes of
+/// calldata from `offset` to memory at `dst`.
+pub fn calldatacopy(
+    stack: &mut Stack,
+    memory: &mut Memory,
+    contract: &Contract,
+) -> Result<(), ExecutionError> {
+    copy(stack, memory, &contract.input)
+}
+
+/// CODESIZE pushes the size of the running code in bytes.
+pub fn codesize(stack: &mut Stack, code: &[u8]) -> Result<(), ExecutionError> {
+    stack.push(U256::from(code.len() as u64));
+    Ok(())
+}
+
+/// CODECOPY pops `dst`, `offset` and `len` and copies `len` bytes of the
+/// running code from `offset` to memory at `dst`.
+pub fn codecopy(stack: &mut Stack, memory: &mut Memory, code: &[u8]) -> Result<(), ExecutionError> {
+    copy(stack, memory, code)
+}
+
+/// EXTCODESIZE pops `address` and pushes the size of the code of that account
+/// in bytes.
+pub fn extcodesize(stack: &mut Stack, state: &impl StateDB) -> Result<(), ExecutionError> {
+    let address = word_to_address(&stack.try_pop()?);
+    stack.push(U256::from(state.get_code_size(&address) as u64));
+    Ok(())
+}
+
+/// EXTCODECOPY pops `address`, `dst`, `offset` and `len` and copies `len`
+/// bytes of the code of that account from `offset` to memory at `dst`.
+pub fn extcodecopy(
+    stack: &mut Stack,
+    memory: &mut Memory,
+    state: &impl StateDB,
+) -> Result<(), ExecutionError> {
+    let address = word_to_address(&stack.try_pop()?);
+    copy(stack, memory, state.get_code(&address))
+}
+
+/// EXTCODEHASH pops `address` and pushes the hash of the code of that account,
+/// or zero if the account is empty (EIP-1052, EIP-161).
+pub fn extcodehash(stack: &mut Stack, state: &impl StateDB) -> Result<(), ExecutionError> {
+    let address = word_to_address(&stack.try_pop()?);
+    if state.empty(&address) {
+        stack.push(U256::ZERO);
+    } else {
+        stack.push(state.get_code_hash(&address));
+    }
+    Ok(())
+}
+
+/// Copy is shared by CALLDATACOPY, CODECOPY and EXTCODECOPY. The memory was
+/// already expanded to cover the destination, so `len` fits in a u64.
+fn copy(stack: &mut Stack, memory: &mut Memory, data: &[u8]) -> Result<(), ExecutionError> {
+    let dst = stack.try_pop()?;
+    let offset = stack.try_pop()?;
+    let len = stack.try_pop()?;
+    let bytes = get_data(data, offset, len.as_usize());
+    memory.try_set_u256(dst, &bytes)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use ethnum::uint;
+
+    use super::*;
+    use crate::{instructions::tests::stack_of, state::MemoryStateDB};
+
+    fn contract() -> Contract {
+        Contract {
+            caller: [0x11; 20],
+            address: [0x22; 20],
+            value: uint!("1000"),
+            input: (1..=40).collect(),
+        }
+    }
+
+    #[test]
+    fn can_push_call_context() {
+        let contract = contract();
+        let mut stack = Stack::new();
+        address(&mut stack, &contract).unwrap();
+        caller(&mut stack, &contract).unwrap();
+        callvalue(&mut stack, &contract).unwrap();
+        calldatasize(&mut stack, &contract).unwrap();
+        assert_eq!(
+            stack.data(),
+            &[
+                address_to_word(&[0x22; 20]),
+                address_to_word(&[0x11; 20]),
+                uint!("1000"),
+                uint!("40")
+            ]
+        );
+    }
+
+    #[test]
+    fn can_calldataload_past_the_end() {
+        let contract = contract();
+        let mut stack = stack_of(&[uint!("8")]);
+        calldataload(&mut stack, &contract).unwrap();
+        let mut expected = [0u8; 32];
+        expected.copy_from_slice(&contract.input[8..40]);
+        assert_eq!(stack.data(), &[U256::from_be_bytes(expected)]);
+
+        let mut stack = stack_of(&[uint!("39")]);
+        calldataload(&mut stack, &contract).unwrap();
+        assert_eq!(stack.data(), &[uint!("40") << 248]);
+
+        let mut stack = stack_of(&[U256::MAX]);
+        calldataload(&mut stack, &contract).unwrap();
+        assert_eq!(stack.data(), &[U256::ZERO]);
+    }
+
+    #[test]
+    fn can_copy_with_padding() {
+        let mut memory = Memory::new();
+        memory.resize(32);
+        // Copy 4 bytes of code from 2 to memory at 1, past the end of the code
+        let mut stack = stack_of(&[uint!("1"), uint!("2"), uint!("4")]);
+        codecopy(&mut stack, &mut memory, &[0xa, 0xb, 0xc, 0xd]).unwrap();
+        assert_eq!(&memory.as_ref()[..6], &[0, 0xc, 0xd, 0, 0, 0]);
+
+        let mut stack = stack_of(&[uint!("0"), U256::MAX, uint!("2")]);
+        calldatacopy(&mut stack, &mut memory, &contract()).unwrap();
+        assert_eq!(&memory.as_ref()[..2], &[0, 0]);
+    }
+
+    #[test]
+    fn can_read_other_accounts() {
+        let bob = [0xbb; 20];
+        let mut state = MemoryStateDB::new();
+        state.add_balance(&bob, uint!("7"));
+        state.set_code(&bob, vec![0xa, 0xb, 0xc]);
+        let word = address_to_word(&bob);
+
+        let mut stack = stack_of(&[word]);
+        balance(&mut stack, &state).unwrap();
+        assert_eq!(stack.data(), &[uint!("7")]);
+        let mut stack = stack_of(&[word]);
+        extcodesize(&mut stack, &state).unwrap();
+        assert_eq!(stack.data(), &[uint!("3")]);
+        let mut stack = stack_of(&[word]);
+        extcodehash(&mut stack, &state).unwrap();
+        assert_eq!(stack.data(), &[state.get_code_hash(&bob)]);
+
+        let mut memory = Memory::new();
+        memory.resize(32);
+        let mut stack = stack_of(&[word, uint!("0"), uint!("1"), uint!("3")]);
+        extcodecopy(&mut stack, &mut memory, &state).unwrap();
+        assert_eq!(&memory.as_ref()[..4], &[0xb, 0xc, 0, 0]);
+
+        // Empty accounts have no code hash
+        let mut stack = stack_of(&[address_to_word(&[0xcc; 20])]);
+        extcodehash(&mut stack, &state).unwrap();
+        assert_eq!(stack.data(), &[U256::ZERO]);
+    }
+
+    #[test]
+    fn can_codesize() {
+        let mut stack = Stack::new();
+        codesize(&mut stack, &[0x00; 7]).unwrap();
+        assert_eq!(stack.data(), &[uint!("7")]);
+    }
+}
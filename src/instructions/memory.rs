@@ -0,0 +1,129 @@
+//! Memory instructions: MLOAD, MSTORE, MSTORE8, MSIZE and MCOPY.
+//!
+//! The interpreter expands the memory before running any of these, so that
+//! the accessed range is always in bounds (see
+//! [`memory_size`](crate::gas::memory_size)).
+
+use ethnum::U256;
+
+use crate::{interpreter::ExecutionError, memory::Memory, stack::Stack};
+
+/// MLOAD pops `offset` and pushes the word in memory starting at `offset`.
+pub fn mload(stack: &mut Stack, memory: &Memory) -> Result<(), ExecutionError> {
+    let offset = stack.try_pop()?;
+    stack.push(memory.try_get_32_u256(offset)?);
+    Ok(())
+}
+
+/// MSTORE pops `offset` and `value` and writes the 32 bytes of `value` in
+/// memory starting at `offset`.
+pub fn mstore(stack: &mut Stack, memory: &mut Memory) -> Result<(), ExecutionError> {
+    let offset = stack.try_pop()?;
+    let value = stack.try_pop()?;
+    memory.try_set_32_u256(offset, value)?;
+    Ok(())
+}
+
+/// MSTORE8 pops `offset` and `value` and writes the least significant byte of
+/// `value` in memory at `offset`.
+pub fn mstore8(stack: &mut Stack, memory: &mut Memory) -> Result<(), ExecutionError> {
+    let offset = stack.try_pop()?;
+    let value = stack.try_pop()?;
+    memory.try_set_u256(offset, &[value.as_u8()])?;
+    Ok(())
+}
+
+/// MSIZE pushes the size of the memory in bytes, which is always a multiple
+/// of 32.
+pub fn msize(stack: &mut Stack, memory: &Memory) -> Result<(), ExecutionError> {
+    stack.push(U256::from(memory.len() as u64));
+    Ok(())
+}
+
+/// MCOPY pops `dst`, `src` and `len` and copies `len` bytes of memory from
+/// `src` to `dst` (EIP-5656). The two ranges may overlap.
+pub fn mcopy(stack: &mut Stack, memory: &mut Memory) -> Result<(), ExecutionError> {
+    let dst = stack.try_pop()?;
+    let src = stack.try_pop()?;
+    let len = stack.try_pop()?;
+    memory.try_copy_u256(dst, src, len)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use ethnum::{uint, U256};
+
+    use super::*;
+    use crate::{instructions::tests::stack_of, memory::MemoryError};
+
+    #[test]
+    fn can_mstore_and_mload() {
+        let mut memory = Memory::new();
+        memory.resize(64);
+        let value = uint!("0x0102030405060708091011121314151617181920212223242526272829303132");
+
+        let mut stack = stack_of(&[uint!("16"), value]);
+        mstore(&mut stack, &mut memory).unwrap();
+        assert_eq!(stack.len(), 0);
+        assert_eq!(memory.as_ref()[16], 0x01);
+        assert_eq!(memory.as_ref()[47], 0x32);
+
+        let mut stack = stack_of(&[uint!("16")]);
+        mload(&mut stack, &memory).unwrap();
+        assert_eq!(stack.data(), &[value]);
+    }
+
+    #[test]
+    fn can_mstore8() {
+        let mut memory = Memory::new();
+        memory.resize(32);
+        let mut stack = stack_of(&[uint!("31"), uint!("0x1234")]);
+        mstore8(&mut stack, &mut memory).unwrap();
+        assert_eq!(stack.len(), 0);
+
+        let mut stack = stack_of(&[uint!("0")]);
+        mload(&mut stack, &memory).unwrap();
+        assert_eq!(stack.data(), &[uint!("0x34")]);
+    }
+
+    #[test]
+    fn can_msize() {
+        let mut memory = Memory::new();
+        memory.resize(64);
+        let mut stack = Stack::new();
+        msize(&mut stack, &memory).unwrap();
+        assert_eq!(stack.data(), &[uint!("64")]);
+    }
+
+    #[test]
+    fn can_mcopy() {
+        let mut memory = Memory::new();
+        memory.resize(64);
+        memory.set_32(0, U256::MAX);
+
+        // Overlapping copy of 32 bytes from 0 to 16
+        let mut stack = stack_of(&[uint!("16"), uint!("0"), uint!("32")]);
+        mcopy(&mut stack, &mut memory).unwrap();
+        assert_eq!(stack.len(), 0);
+        assert_eq!(memory.as_ref(), [vec![0xff; 48], vec![0x00; 16]].concat());
+    }
+
+    #[test]
+    fn cannot_mstore_without_expanding_memory() {
+        let mut memory = Memory::new();
+        let mut stack = stack_of(&[uint!("0"), uint!("1")]);
+        assert!(matches!(
+            mstore(&mut stack, &mut memory),
+            Err(ExecutionError::Memory(MemoryError::OutOfBounds { .. }))
+        ));
+    }
+
+    #[test]
+    fn can_mcopy_nothing_from_anywhere() {
+        let mut memory = Memory::new();
+        let mut stack = stack_of(&[U256::MAX, U256::MAX, uint!("0")]);
+        mcopy(&mut stack, &mut memory).unwrap();
+        assert_eq!(memory.len(), 0);
+    }
+}
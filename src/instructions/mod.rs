@@ -0,0 +1,47 @@
+//! Instructions are the handlers of the opcodes. They are the counterpart of
+//! geth's `instructions.go`.
+//!
+//! Every handler is a standalone function operating on the pieces of the
+//! machine state it needs (e.g. only the [`Stack`](crate::stack::Stack) for
+//! arithmetic), so that it can be tested without running any bytecode.
+
+pub mod arithmetic;
+pub mod bitwise;
+pub mod control;
+pub mod crypto;
+pub mod environment;
+pub mod memory;
+pub mod stack;
+pub mod storage;
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use ethnum::{uint, U256};
+
+    use crate::{interpreter::ExecutionError, stack::Stack};
+
+    pub const MAX: U256 = U256::MAX;
+    /// -2^255 in two's complement
+    pub const MIN_SIGNED: U256 =
+        uint!("0x8000000000000000000000000000000000000000000000000000000000000000");
+
+    /// Build a stack holding `args`, the first argument on top, as the
+    /// operands of an instruction.
+    pub fn stack_of(args: &[U256]) -> Stack {
+        let mut stack = Stack::new();
+        for arg in args.iter().rev() {
+            stack.push(*arg);
+        }
+        stack
+    }
+
+    /// Run an instruction taking its operands from the stack and pushing a
+    /// single result, and return that result. The first argument is pushed
+    /// last, so it is the topmost element when the instruction runs.
+    pub fn run(instruction: fn(&mut Stack) -> Result<(), ExecutionError>, args: &[U256]) -> U256 {
+        let mut stack = stack_of(args);
+        instruction(&mut stack).unwrap();
+        assert_eq!(stack.len(), 1);
+        stack.pop().unwrap()
+    }
+}
@@ -0,0 +1,163 @@
+//! Stack instructions: POP, PUSH0 to PUSH32, DUP1 to DUP16 and SWAP1 to
+//! SWAP16.
+
+use ethnum::U256;
+
+use crate::{interpreter::ExecutionError, stack::Stack};
+
+/// POP pops the topmost item and discards it.
+pub fn pop(stack: &mut Stack) -> Result<(), ExecutionError> {
+    stack.try_pop()?;
+    Ok(())
+}
+
+/// PUSHn pushes the `size` bytes following the opcode at `pc` as a big-endian
+/// word, and moves `pc` to the last of them.
+///
+/// Like geth's `makePush`, an immediate cut short by the end of the code is
+/// padded with zeros on the right, as if the code went on with STOPs.
+pub fn push(
+    stack: &mut Stack,
+    code: &[u8],
+    pc: &mut usize,
+    size: usize,
+) -> Result<(), ExecutionError> {
+    let start = (*pc + 1).min(code.len());
+    let end = (start + size).min(code.len());
+
+    let mut bytes = [0u8; 32];
+    let padded = &mut bytes[32 - size..];
+    padded[..end - start].copy_from_slice(&code[start..end]);
+    stack.try_push(U256::from_be_bytes(bytes))?;
+    *pc += size;
+    Ok(())
+}
+
+/// DUPn pushes a copy of the nth item from the top.
+/// Note that `n` starts from 1 (not 0)
+pub fn dup(stack: &mut Stack, n: usize) -> Result<(), ExecutionError> {
+    stack.try_dup(n)?;
+    Ok(())
+}
+
+/// SWAPn swaps the topmost item with the one n items below it, i.e. SWAP1
+/// swaps the two topmost items.
+pub fn swap(stack: &mut Stack, n: usize) -> Result<(), ExecutionError> {
+    stack.try_swap(n + 1)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use ethnum::uint;
+
+    use super::*;
+    use crate::{instructions::tests::stack_of, opcodes::Opcode, stack::StackError};
+
+    #[test]
+    fn can_push() {
+        // PUSH2 0x0102, STOP
+        let code = [0x61, 0x01, 0x02, 0x00];
+        let mut stack = Stack::new();
+        let mut pc = 0;
+        push(&mut stack, &code, &mut pc, 2).unwrap();
+        assert_eq!(stack.data(), &[uint!("0x0102")]);
+        assert_eq!(pc, 2);
+    }
+
+    #[test]
+    fn can_push0() {
+        let mut stack = Stack::new();
+        let mut pc = 0;
+        push(&mut stack, &[0x5f], &mut pc, 0).unwrap();
+        assert_eq!(stack.data(), &[uint!("0")]);
+        assert_eq!(pc, 0);
+    }
+
+    #[test]
+    fn can_push32() {
+        let mut code = vec![0x7f];
+        code.extend([0xff; 32]);
+        let mut stack = Stack::new();
+        let mut pc = 0;
+        push(&mut stack, &code, &mut pc, 32).unwrap();
+        assert_eq!(stack.data(), &[U256::MAX]);
+        assert_eq!(pc, 32);
+    }
+
+    #[test]
+    fn can_push_truncated_immediate() {
+        // PUSH3 0x0102, with the code ending in the middle of the immediate
+        let mut stack = Stack::new();
+        let mut pc = 0;
+        push(&mut stack, &[0x62, 0x01, 0x02], &mut pc, 3).unwrap();
+        assert_eq!(stack.data(), &[uint!("0x010200")]);
+        assert_eq!(pc, 3);
+
+        // PUSH2 as the very last byte
+        let mut stack = Stack::new();
+        let mut pc = 0;
+        push(&mut stack, &[0x61], &mut pc, 2).unwrap();
+        assert_eq!(stack.data(), &[uint!("0")]);
+    }
+
+    #[test]
+    fn can_get_push_size() {
+        assert_eq!(Opcode::PUSH0.push_size(), 0);
+        assert_eq!(Opcode::PUSH1.push_size(), 1);
+        assert_eq!(Opcode::PUSH32.push_size(), 32);
+        assert_eq!(Opcode::ADD.push_size(), 0);
+        assert_eq!(Opcode::DUP1.push_size(), 0);
+    }
+
+    #[test]
+    fn can_pop() {
+        let mut stack = stack_of(&[uint!("1"), uint!("2")]);
+        pop(&mut stack).unwrap();
+        assert_eq!(stack.data(), &[uint!("2")]);
+    }
+
+    #[test]
+    fn can_dup() {
+        let mut stack = stack_of(&[uint!("1"), uint!("2"), uint!("3")]);
+        dup(&mut stack, 1).unwrap();
+        assert_eq!(stack.peek(), Some(&uint!("1")));
+        dup(&mut stack, 4).unwrap();
+        assert_eq!(stack.peek(), Some(&uint!("3")));
+        assert_eq!(stack.len(), 5);
+    }
+
+    #[test]
+    fn cannot_dup_past_the_bottom() {
+        let mut stack = stack_of(&[uint!("1"), uint!("2")]);
+        assert!(matches!(
+            dup(&mut stack, 3),
+            Err(ExecutionError::Stack(StackError::StackUnderflow {
+                required: 3,
+                available: 2
+            }))
+        ));
+        assert_eq!(stack.len(), 2);
+    }
+
+    #[test]
+    fn can_swap() {
+        let mut stack = stack_of(&[uint!("1"), uint!("2"), uint!("3")]);
+        swap(&mut stack, 1).unwrap();
+        assert_eq!(stack.data(), &[uint!("3"), uint!("1"), uint!("2")]);
+        swap(&mut stack, 2).unwrap();
+        assert_eq!(stack.data(), &[uint!("2"), uint!("1"), uint!("3")]);
+    }
+
+    #[test]
+    fn cannot_swap_past_the_bottom() {
+        let mut stack = stack_of(&[uint!("1"), uint!("2")]);
+        assert!(matches!(
+            swap(&mut stack, 2),
+            Err(ExecutionError::Stack(StackError::StackUnderflow {
+                required: 3,
+                available: 2
+            }))
+        ));
+    }
+}
@@ -0,0 +1,102 @@
+//! Storage instructions: SLOAD, SSTORE, TLOAD and TSTORE.
+//!
+//! The gas of SLOAD and SSTORE depends on the state of the slot, and is
+//! charged before they run (see [`sstore_gas`](crate::gas::sstore_gas)). The
+//! transient storage of TLOAD and TSTORE always costs the same (EIP-1153).
+
+use crate::{contract::Address, interpreter::ExecutionError, stack::Stack, state::StateDB};
+
+/// SLOAD pops `key` and pushes the value of that storage slot of the account
+/// whose code runs.
+pub fn sload(
+    stack: &mut Stack,
+    state: &impl StateDB,
+    address: &Address,
+) -> Result<(), ExecutionError> {
+    let key = stack.try_pop()?;
+    stack.push(state.get_state(address, &key));
+    Ok(())
+}
+
+/// SSTORE pops `key` and `value` and writes `value` to that storage slot of
+/// the account whose code runs.
+pub fn sstore(
+    stack: &mut Stack,
+    state: &mut impl StateDB,
+    address: &Address,
+) -> Result<(), ExecutionError> {
+    let key = stack.try_pop()?;
+    let value = stack.try_pop()?;
+    state.set_state(address, key, value);
+    Ok(())
+}
+
+/// TLOAD pops `key` and pushes the value of that transient storage slot of
+/// the account whose code runs.
+pub fn tload(
+    stack: &mut Stack,
+    state: &impl StateDB,
+    address: &Address,
+) -> Result<(), ExecutionError> {
+    let key = stack.try_pop()?;
+    stack.push(state.get_transient_state(address, &key));
+    Ok(())
+}
+
+/// TSTORE pops `key` and `value` and writes `value` to that transient storage
+/// slot of the account whose code runs.
+pub fn tstore(
+    stack: &mut Stack,
+    state: &mut impl StateDB,
+    address: &Address,
+) -> Result<(), ExecutionError> {
+    let key = stack.try_pop()?;
+    let value = stack.try_pop()?;
+    state.set_transient_state(address, key, value);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use ethnum::{uint, U256};
+
+    use super::*;
+    use crate::{instructions::tests::stack_of, state::MemoryStateDB};
+
+    const ADDRESS: Address = [0xaa; 20];
+
+    #[test]
+    fn can_sstore_and_sload() {
+        let mut state = MemoryStateDB::new();
+        let mut stack = stack_of(&[uint!("1"), uint!("42")]);
+        sstore(&mut stack, &mut state, &ADDRESS).unwrap();
+        assert_eq!(stack.len(), 0);
+        assert_eq!(state.get_state(&ADDRESS, &uint!("1")), uint!("42"));
+
+        let mut stack = stack_of(&[uint!("1")]);
+        sload(&mut stack, &state, &ADDRESS).unwrap();
+        assert_eq!(stack.data(), &[uint!("42")]);
+
+        // Other accounts have storage of their own
+        let mut stack = stack_of(&[uint!("1")]);
+        sload(&mut stack, &state, &[0xbb; 20]).unwrap();
+        assert_eq!(stack.data(), &[U256::ZERO]);
+    }
+
+    #[test]
+    fn can_tstore_and_tload() {
+        let mut state = MemoryStateDB::new();
+        let mut stack = stack_of(&[uint!("1"), uint!("42")]);
+        tstore(&mut stack, &mut state, &ADDRESS).unwrap();
+        assert_eq!(stack.len(), 0);
+
+        let mut stack = stack_of(&[uint!("1")]);
+        tload(&mut stack, &state, &ADDRESS).unwrap();
+        assert_eq!(stack.data(), &[uint!("42")]);
+
+        // Transient storage is apart from storage
+        let mut stack = stack_of(&[uint!("1")]);
+        sload(&mut stack, &state, &ADDRESS).unwrap();
+        assert_eq!(stack.data(), &[U256::ZERO]);
+    }
+}
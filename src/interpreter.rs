@@ -0,0 +1,1185 @@
+use std::rc::Rc;
+
+use ethnum::U256;
+use thiserror::Error;
+
+use crate::{
+    analysis::{code_bitmap, Bitvec},
+    contract::{word_to_address, Contract},
+    crypto::{DefaultHasher, Hasher, PreimageCache},
+    fork::Fork,
+    gas::{self, GasMeter, GasUintOverflow, OutOfGas, ReentrancySentry},
+    instructions::{arithmetic, bitwise, control, crypto, environment, memory, stack, storage},
+    jump_table::JumpTable,
+    memory::{Memory, MemoryError},
+    opcodes::{InvalidOpcode, Opcode},
+    stack::{Stack, StackError},
+    state::{MemoryStateDB, StateDB},
+    tracing::{NoopTracer, Step, Tracer},
+};
+
+/// Errors that abort the execution of a piece of bytecode. These mirror the
+/// errors declared in geth's `core/vm/errors.go`.
+///
+/// Unlike a REVERT, which is a regular (although unsuccessful) outcome of the
+/// execution, any of these errors consumes all the gas given to the call.
+#[derive(Error, Debug)]
+pub enum ExecutionError {
+    #[error(transparent)]
+    InvalidOpcode(#[from] InvalidOpcode),
+    #[error(transparent)]
+    Stack(#[from] StackError),
+    #[error(transparent)]
+    OutOfGas(#[from] OutOfGas),
+    #[error(transparent)]
+    GasUintOverflow(#[from] GasUintOverflow),
+    #[error(transparent)]
+    Memory(#[from] MemoryError),
+    #[error(transparent)]
+    ReentrancySentry(#[from] ReentrancySentry),
+    #[error("write protection")]
+    WriteProtection,
+    #[error("invalid jump destination {dest:#x}")]
+    InvalidJump { dest: U256 },
+    #[error("opcode {byte:#04x} is not supported yet")]
+    Unsupported { byte: u8 },
+}
+
+/// The outcome of a successful run of the interpreter.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ExecutionResult {
+    /// Execution hit STOP, or ran past the end of the code (which is an
+    /// implicit STOP).
+    Stop,
+    /// Execution hit RETURN. Holds the returned memory range.
+    Return(Vec<u8>),
+    /// Execution hit REVERT. Holds the returned memory range, which usually
+    /// encodes the revert reason.
+    Revert(Vec<u8>),
+}
+
+/// Interpreter runs a piece of bytecode. It is the counterpart of
+/// `EVMInterpreter` in geth's `interpreter.go`.
+///
+/// The interpreter walks the code one instruction at a time: it reads the byte
+/// under the program counter, decodes it into an [`Opcode`], and dispatches it
+/// to the handler operating on the [`Stack`] and the [`Memory`]. It keeps going
+/// until a handler halts the execution or an error occurs.
+///
+/// The code runs against the world state `S`, which is an in-memory state
+/// unless another one is given with [`Interpreter::with_state`].
+pub struct Interpreter<S: StateDB = MemoryStateDB> {
+    /// The bytecode being executed
+    code: Vec<u8>,
+    /// The program counter, i.e. the offset in `code` of the next instruction
+    pc: usize,
+    stack: Stack,
+    memory: Memory,
+    gas: GasMeter,
+    /// The JUMPDEST analysis of `code`, computed on the first jump unless it
+    /// was given with [`Interpreter::with_analysis`]
+    analysis: Option<Rc<Bitvec>>,
+    /// The instruction set of the fork whose rules apply
+    jump_table: JumpTable,
+    /// The call being run
+    contract: Contract,
+    /// The Keccak-256 implementation of KECCAK256
+    hasher: Box<dyn Hasher>,
+    /// The preimages of the hashes computed by KECCAK256, if they are
+    /// recorded
+    preimages: Option<PreimageCache>,
+    /// The world state
+    state: S,
+    /// Whether the call is static (e.g. a STATICCALL), in which case opcodes
+    /// writing to the state fail
+    read_only: bool,
+}
+
+impl Interpreter {
+    /// Create an interpreter running `code` with `gas` available.
+    pub fn new(code: &[u8], gas: u64) -> Self {
+        Interpreter {
+            code: code.to_vec(),
+            pc: 0,
+            stack: Stack::new(),
+            memory: Memory::new(),
+            gas: GasMeter::new(gas),
+            analysis: None,
+            jump_table: JumpTable::new(Fork::LATEST),
+            contract: Contract::default(),
+            hasher: Box::<DefaultHasher>::default(),
+            preimages: None,
+            state: MemoryStateDB::new(),
+            read_only: false,
+        }
+    }
+}
+
+impl<S: StateDB> Interpreter<S> {
+    /// Run the code against `state`, instead of an empty in-memory state.
+    pub fn with_state<T: StateDB>(self, state: T) -> Interpreter<T> {
+        Interpreter {
+            code: self.code,
+            pc: self.pc,
+            stack: self.stack,
+            memory: self.memory,
+            gas: self.gas,
+            analysis: self.analysis,
+            jump_table: self.jump_table,
+            contract: self.contract,
+            hasher: self.hasher,
+            preimages: self.preimages,
+            state,
+            read_only: self.read_only,
+        }
+    }
+
+    /// Run the code in the call described by `contract`, instead of a call
+    /// with no value nor input between zero addresses.
+    pub fn with_contract(mut self, contract: Contract) -> Self {
+        self.contract = contract;
+        self
+    }
+
+    /// Run the code with the rules of `fork`, instead of the latest fork.
+    pub fn with_fork(mut self, fork: Fork) -> Self {
+        self.jump_table = JumpTable::new(fork);
+        self
+    }
+
+    /// Use `analysis` as the JUMPDEST analysis of the code, e.g. from a
+    /// [`JumpDestCache`](crate::analysis::JumpDestCache), instead of
+    /// analysing the code again. `analysis` must be the bitmap of the code.
+    pub fn with_analysis(mut self, analysis: Rc<Bitvec>) -> Self {
+        self.analysis = Some(analysis);
+        self
+    }
+
+    /// Run the code in a static context, as if it were called by STATICCALL
+    /// (EIP-214): the opcodes writing to the state fail.
+    pub fn with_read_only(mut self) -> Self {
+        self.read_only = true;
+        self
+    }
+
+    /// Hash with `hasher` instead of the [`DefaultHasher`].
+    pub fn with_hasher(mut self, hasher: impl Hasher + 'static) -> Self {
+        self.hasher = Box::new(hasher);
+        self
+    }
+
+    /// Record the preimage of every hash computed by KECCAK256, so that they
+    /// can be read back with [`Interpreter::preimages`].
+    pub fn with_preimage_recording(mut self) -> Self {
+        self.preimages = Some(PreimageCache::new());
+        self
+    }
+
+    /// Get a reference to the stack
+    pub fn stack(&self) -> &Stack {
+        &self.stack
+    }
+
+    /// Get a reference to the memory
+    pub fn memory(&self) -> &Memory {
+        &self.memory
+    }
+
+    /// Get the current program counter
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    /// Get a reference to the gas meter
+    pub fn gas(&self) -> &GasMeter {
+        &self.gas
+    }
+
+    /// Get the fork whose rules apply
+    pub fn fork(&self) -> Fork {
+        self.jump_table.fork()
+    }
+
+    /// Get a reference to the call being run
+    pub fn contract(&self) -> &Contract {
+        &self.contract
+    }
+
+    /// Get a reference to the world state
+    pub fn state(&self) -> &S {
+        &self.state
+    }
+
+    /// Get a mutable reference to the world state, e.g. to set it up between
+    /// runs
+    pub fn state_mut(&mut self) -> &mut S {
+        &mut self.state
+    }
+
+    /// GasRefund returns the gas refunded to the transaction so far, capped
+    /// as it would be if the transaction ended now (EIP-3529). Unlike the
+    /// refund counter of the state, it never exceeds a fraction of the gas
+    /// used.
+    pub fn gas_refund(&self) -> u64 {
+        let cap = gas::max_refund(self.gas.used(), self.jump_table.fork());
+        self.state.get_refund().min(cap)
+    }
+
+    /// Get the recorded preimages, or None if they are not recorded
+    pub fn preimages(&self) -> Option<&PreimageCache> {
+        self.preimages.as_ref()
+    }
+
+    /// Get the code being run
+    pub fn code(&self) -> &[u8] {
+        &self.code
+    }
+
+    /// Run loops and evaluates the code until it halts or an error occurs.
+    ///
+    /// If an error occurs, all the gas is consumed. A REVERT, on the other
+    /// hand, only consumes the gas used so far. Either way, the changes made
+    /// to the state by the run are reverted.
+    pub fn run(&mut self) -> Result<ExecutionResult, ExecutionError> {
+        self.run_with_tracer(&mut NoopTracer)
+    }
+
+    /// RunWithTracer runs the code like [`Interpreter::run`], and reports
+    /// every step of the execution to `tracer`.
+    pub fn run_with_tracer(
+        &mut self,
+        tracer: &mut dyn Tracer,
+    ) -> Result<ExecutionResult, ExecutionError> {
+        tracer.capture_start(&self.code, &self.contract, self.gas.remaining());
+        let snapshot = self.state.snapshot();
+        let result = loop {
+            match self.step_with_tracer(tracer) {
+                Ok(Some(result)) => break Ok(result),
+                Ok(None) => {}
+                Err(err) => break Err(err),
+            }
+        };
+        if matches!(result, Ok(ExecutionResult::Revert(_)) | Err(_)) {
+            self.state.revert_to_snapshot(snapshot);
+        }
+        match &result {
+            Ok(ExecutionResult::Stop) => tracer.capture_end(&[], self.gas.used(), None),
+            Ok(ExecutionResult::Return(output) | ExecutionResult::Revert(output)) => {
+                tracer.capture_end(output, self.gas.used(), None)
+            }
+            Err(err) => tracer.capture_end(&[], self.gas.used(), Some(err)),
+        }
+        result
+    }
+
+    /// Step runs the instruction under the program counter. It returns
+    /// `Some(result)` once the execution halted, and consumes all the gas if
+    /// an error occurs, like [`Interpreter::run`].
+    ///
+    /// Stepping again after the execution halted runs whatever follows the
+    /// halting instruction. Unlike [`Interpreter::run`], stepping never
+    /// reverts the state: the caller can take a snapshot of the state first.
+    pub fn step(&mut self) -> Result<Option<ExecutionResult>, ExecutionError> {
+        self.step_with_tracer(&mut NoopTracer)
+    }
+
+    fn step_with_tracer(
+        &mut self,
+        tracer: &mut dyn Tracer,
+    ) -> Result<Option<ExecutionResult>, ExecutionError> {
+        let result = self.try_step(tracer);
+        if result.is_err() {
+            self.gas.consume_all();
+        }
+        result
+    }
+
+    fn try_step(
+        &mut self,
+        tracer: &mut dyn Tracer,
+    ) -> Result<Option<ExecutionResult>, ExecutionError> {
+        // Running off the end of the code is the same as running STOP.
+        let byte = match self.code.get(self.pc) {
+            Some(byte) => *byte,
+            None => return Ok(Some(ExecutionResult::Stop)),
+        };
+        let gas = self.gas.remaining();
+        let (op, memory_size) = match self.charge(byte) {
+            Ok(charged) => charged,
+            Err(err) => {
+                tracer.capture_fault(&self.trace_step(byte, gas), &err);
+                return Err(err);
+            }
+        };
+        // Like geth, the state is captured before the memory expands.
+        tracer.capture_state(&self.trace_step(byte, gas));
+        self.memory.resize(memory_size as usize);
+
+        let pc = self.pc;
+        self.execute(op).map_err(|err| {
+            self.pc = pc;
+            tracer.capture_fault(&self.trace_step(byte, gas), &err);
+            err
+        })
+    }
+
+    /// Charge decodes `byte` and charges everything the instruction costs,
+    /// before it runs. It returns the decoded opcode and the size the memory
+    /// must grow to.
+    fn charge(&mut self, byte: u8) -> Result<(Opcode, u64), ExecutionError> {
+        let op = self.jump_table.decode(byte)?;
+        // Validate the stack up front, so that handlers never run against a
+        // stack that is too short or too long for them.
+        self.stack.validate(op.min_stack(), op.max_stack())?;
+        if self.read_only && op.info().writes {
+            return Err(ExecutionError::WriteProtection);
+        }
+        // Charge the gas before running the opcode, so that the execution
+        // halts before doing any work it cannot pay for.
+        self.gas.charge(self.jump_table.constant_gas(&op))?;
+        // Opcodes accessing memory pay for growing it before running, so that
+        // handlers can assume the memory is large enough.
+        let memory_size = self.memory_size(&op)?;
+        let memory_gas = self.memory.gas_cost(memory_size)?;
+        self.gas.charge(memory_gas)?;
+        let dynamic_gas = self.dynamic_gas(&op)?;
+        self.gas.charge(dynamic_gas)?;
+        Ok((op, memory_size))
+    }
+
+    /// TraceStep captures the state of the machine for the tracers, for the
+    /// instruction `byte` which started with `gas` available. See [`Step`] for
+    /// why the depth and the return data are constant.
+    fn trace_step(&self, byte: u8, gas: u64) -> Step<'_> {
+        Step {
+            pc: self.pc,
+            op: byte,
+            gas,
+            cost: gas - self.gas.remaining(),
+            depth: 1,
+            stack: self.stack.data(),
+            memory: self.memory.as_ref(),
+            return_data: &[],
+            refund: self.state.get_refund(),
+        }
+    }
+
+    /// Execute dispatches a single opcode to its handler, and moves the
+    /// program counter to the next instruction. It returns `Some(result)` if
+    /// the opcode halted the execution.
+    fn execute(&mut self, op: Opcode) -> Result<Option<ExecutionResult>, ExecutionError> {
+        let byte = op.byte();
+        match op {
+            Opcode::STOP => return Ok(Some(ExecutionResult::Stop)),
+            Opcode::ADD => arithmetic::add(&mut self.stack)?,
+            Opcode::MUL => arithmetic::mul(&mut self.stack)?,
+            Opcode::SUB => arithmetic::sub(&mut self.stack)?,
+            Opcode::DIV => arithmetic::div(&mut self.stack)?,
+            Opcode::SDIV => arithmetic::sdiv(&mut self.stack)?,
+            Opcode::MOD => arithmetic::modulo(&mut self.stack)?,
+            Opcode::SMOD => arithmetic::smod(&mut self.stack)?,
+            Opcode::ADDMOD => arithmetic::addmod(&mut self.stack)?,
+            Opcode::MULMOD => arithmetic::mulmod(&mut self.stack)?,
+            Opcode::EXP => arithmetic::exp(&mut self.stack)?,
+            Opcode::SIGNEXTEND => arithmetic::signextend(&mut self.stack)?,
+
+            Opcode::LT => bitwise::lt(&mut self.stack)?,
+            Opcode::GT => bitwise::gt(&mut self.stack)?,
+            Opcode::SLT => bitwise::slt(&mut self.stack)?,
+            Opcode::SGT => bitwise::sgt(&mut self.stack)?,
+            Opcode::EQ => bitwise::eq(&mut self.stack)?,
+            Opcode::ISZERO => bitwise::iszero(&mut self.stack)?,
+            Opcode::AND => bitwise::and(&mut self.stack)?,
+            Opcode::OR => bitwise::or(&mut self.stack)?,
+            Opcode::XOR => bitwise::xor(&mut self.stack)?,
+            Opcode::NOT => bitwise::not(&mut self.stack)?,
+            Opcode::BYTE => bitwise::byte(&mut self.stack)?,
+            Opcode::SHL => bitwise::shl(&mut self.stack)?,
+            Opcode::SHR => bitwise::shr(&mut self.stack)?,
+            Opcode::SAR => bitwise::sar(&mut self.stack)?,
+
+            Opcode::KECCAK256 => crypto::keccak256(
+                &mut self.stack,
+                &self.memory,
+                self.hasher.as_ref(),
+                self.preimages.as_mut(),
+            )?,
+
+            Opcode::ADDRESS => environment::address(&mut self.stack, &self.contract)?,
+            Opcode::BALANCE => environment::balance(&mut self.stack, &self.state)?,
+            Opcode::CALLER => environment::caller(&mut self.stack, &self.contract)?,
+            Opcode::CALLVALUE => environment::callvalue(&mut self.stack, &self.contract)?,
+            Opcode::CALLDATALOAD => environment::calldataload(&mut self.stack, &self.contract)?,
+            Opcode::CALLDATASIZE => environment::calldatasize(&mut self.stack, &self.contract)?,
+            Opcode::CALLDATACOPY => {
+                environment::calldatacopy(&mut self.stack, &mut self.memory, &self.contract)?
+            }
+            Opcode::CODESIZE => environment::codesize(&mut self.stack, &self.code)?,
+            Opcode::CODECOPY => {
+                environment::codecopy(&mut self.stack, &mut self.memory, &self.code)?
+            }
+            Opcode::EXTCODESIZE => environment::extcodesize(&mut self.stack, &self.state)?,
+            Opcode::EXTCODECOPY => {
+                environment::extcodecopy(&mut self.stack, &mut self.memory, &self.state)?
+            }
+            Opcode::EXTCODEHASH => environment::extcodehash(&mut self.stack, &self.state)?,
+
+            Opcode::MLOAD => memory::mload(&mut self.stack, &self.memory)?,
+            Opcode::MSTORE => memory::mstore(&mut self.stack, &mut self.memory)?,
+            Opcode::MSTORE8 => memory::mstore8(&mut self.stack, &mut self.memory)?,
+            Opcode::MSIZE => memory::msize(&mut self.stack, &self.memory)?,
+            Opcode::MCOPY => memory::mcopy(&mut self.stack, &mut self.memory)?,
+
+            Opcode::JUMP => {
+                let analysis = self.analysis();
+                self.pc = control::jump(&mut self.stack, &self.code, &analysis)?;
+                return Ok(None);
+            }
+            Opcode::JUMPI => {
+                let analysis = self.analysis();
+                if let Some(dest) = control::jumpi(&mut self.stack, &self.code, &analysis)? {
+                    self.pc = dest;
+                    return Ok(None);
+                }
+            }
+            Opcode::PC => control::pc(&mut self.stack, self.pc)?,
+            Opcode::GAS => control::gas(&mut self.stack, &self.gas)?,
+            // JUMPDEST only marks a valid destination for the jumps
+            Opcode::JUMPDEST => {}
+
+            Opcode::POP => stack::pop(&mut self.stack)?,
+            Opcode::SLOAD => storage::sload(&mut self.stack, &self.state, &self.contract.address)?,
+            Opcode::SSTORE => {
+                storage::sstore(&mut self.stack, &mut self.state, &self.contract.address)?
+            }
+            Opcode::TLOAD => storage::tload(&mut self.stack, &self.state, &self.contract.address)?,
+            Opcode::TSTORE => {
+                storage::tstore(&mut self.stack, &mut self.state, &self.contract.address)?
+            }
+            Opcode::RETURN => return Ok(Some(ExecutionResult::Return(self.op_return()?))),
+            Opcode::REVERT => return Ok(Some(ExecutionResult::Revert(self.op_return()?))),
+            Opcode::INVALID => return Err(InvalidOpcode::new(byte).into()),
+            _ if op.is_push() => {
+                stack::push(&mut self.stack, &self.code, &mut self.pc, op.push_size())?
+            }
+            // DUP1 - DUP16
+            _ if (0x80..=0x8f).contains(&byte) => {
+                stack::dup(&mut self.stack, (byte - 0x7f) as usize)?
+            }
+            // SWAP1 - SWAP16
+            _ if (0x90..=0x9f).contains(&byte) => {
+                stack::swap(&mut self.stack, (byte - 0x8f) as usize)?
+            }
+            _ => return Err(ExecutionError::Unsupported { byte }),
+        }
+        // Handlers that read immediates (e.g. PUSH) already moved the program
+        // counter past them, so we only have to skip the opcode.
+        self.pc += 1;
+        Ok(None)
+    }
+
+    /// Analysis returns the JUMPDEST analysis of the code, analysing it the
+    /// first time it is needed.
+    fn analysis(&mut self) -> Rc<Bitvec> {
+        self.analysis
+            .get_or_insert_with(|| Rc::new(code_bitmap(&self.code)))
+            .clone()
+    }
+
+    /// Memory size returns the size the memory must have for the opcode to
+    /// run. The stack was already validated, so the operands are there.
+    fn memory_size(&self, op: &Opcode) -> Result<u64, GasUintOverflow> {
+        let arg = |n| *self.stack.back(n).unwrap();
+        match *op {
+            Opcode::MLOAD | Opcode::MSTORE => gas::memory_size(arg(0), U256::from(32u8)),
+            Opcode::MSTORE8 => gas::memory_size(arg(0), U256::ONE),
+            Opcode::MCOPY => {
+                let dst = gas::memory_size(arg(0), arg(2))?;
+                let src = gas::memory_size(arg(1), arg(2))?;
+                Ok(dst.max(src))
+            }
+            Opcode::CALLDATACOPY | Opcode::CODECOPY => gas::memory_size(arg(0), arg(2)),
+            Opcode::EXTCODECOPY => gas::memory_size(arg(1), arg(3)),
+            Opcode::KECCAK256 | Opcode::RETURN | Opcode::REVERT => gas::memory_size(arg(0), arg(1)),
+            _ => Ok(0),
+        }
+    }
+
+    /// Dynamic gas returns the part of the cost of an opcode that depends on
+    /// its operands, or on the state. The stack was already validated, so the
+    /// operands are there.
+    ///
+    /// Like in geth, computing the cost of the storage opcodes also warms the
+    /// accessed slots up and updates the refund counter, and computing the
+    /// cost of the opcodes reading other accounts warms these accounts up.
+    /// These changes stay even if the opcode then cannot pay for them:
+    /// [`Interpreter::run`] reverts them along with the rest of the run.
+    fn dynamic_gas(&mut self, op: &Opcode) -> Result<u64, ExecutionError> {
+        let arg = |n| *self.stack.back(n).unwrap();
+        let fork = self.jump_table.fork();
+        let address = &self.contract.address;
+        Ok(match *op {
+            Opcode::EXP => gas::exp_gas(&arg(1), fork),
+            Opcode::KECCAK256 => gas::keccak256_gas(&arg(1)),
+            Opcode::MCOPY | Opcode::CALLDATACOPY | Opcode::CODECOPY => gas::copy_gas(&arg(2)),
+            Opcode::BALANCE | Opcode::EXTCODESIZE | Opcode::EXTCODEHASH => {
+                gas::account_access_gas(&mut self.state, &word_to_address(&arg(0)), fork)
+            }
+            Opcode::EXTCODECOPY => {
+                let access =
+                    gas::account_access_gas(&mut self.state, &word_to_address(&arg(0)), fork);
+                gas::copy_gas(&arg(3)) + access
+            }
+            Opcode::SLOAD => gas::sload_gas(&mut self.state, address, &arg(0), fork),
+            Opcode::SSTORE => {
+                let gas_left = self.gas.remaining();
+                gas::sstore_gas(&mut self.state, address, &arg(0), &arg(1), gas_left, fork)?
+            }
+            _ => 0,
+        })
+    }
+
+    fn pop(&mut self) -> Result<U256, ExecutionError> {
+        Ok(self.stack.try_pop()?)
+    }
+
+    /// Pop `offset` and `size` and return a copy of that memory range. It is
+    /// shared by RETURN and REVERT.
+    ///
+    /// The memory was already expanded to cover the range.
+    fn op_return(&mut self) -> Result<Vec<u8>, ExecutionError> {
+        let offset = self.pop()?;
+        let size = self.pop()?;
+        Ok(self.memory.try_get_u256(offset, size)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ethnum::uint;
+
+    use super::*;
+    use crate::access_list::AccessTuple;
+
+    const GAS: u64 = 1_000_000;
+
+    #[test]
+    fn can_run_empty_code() {
+        let mut interpreter = Interpreter::new(&[], GAS);
+        assert_eq!(interpreter.run().unwrap(), ExecutionResult::Stop);
+    }
+
+    #[test]
+    fn can_stop() {
+        // PUSH1 0x01, STOP, PUSH1 0x02
+        let mut interpreter = Interpreter::new(&[0x60, 0x01, 0x00, 0x60, 0x02], GAS);
+        assert_eq!(interpreter.run().unwrap(), ExecutionResult::Stop);
+        assert_eq!(interpreter.stack().data(), &[uint!("1")]);
+        assert_eq!(interpreter.pc(), 2);
+    }
+
+    #[test]
+    fn can_push_and_pop() {
+        // PUSH2 0x0102, PUSH1 0x03, POP
+        let mut interpreter = Interpreter::new(&[0x61, 0x01, 0x02, 0x60, 0x03, 0x50], GAS);
+        assert_eq!(interpreter.run().unwrap(), ExecutionResult::Stop);
+        assert_eq!(interpreter.stack().data(), &[uint!("0x0102")]);
+    }
+
+    #[test]
+    fn can_run_arithmetic() {
+        // PUSH1 0x02, PUSH1 0x03, PUSH1 0x04, ADD, MUL -> (4 + 3) * 2
+        let mut interpreter =
+            Interpreter::new(&[0x60, 0x02, 0x60, 0x03, 0x60, 0x04, 0x01, 0x02], GAS);
+        assert_eq!(interpreter.run().unwrap(), ExecutionResult::Stop);
+        assert_eq!(interpreter.stack().data(), &[uint!("14")]);
+    }
+
+    #[test]
+    fn can_run_comparison() {
+        // PUSH1 0x02, PUSH1 0x01, LT, ISZERO -> !(1 < 2)
+        let mut interpreter = Interpreter::new(&[0x60, 0x02, 0x60, 0x01, 0x10, 0x15], GAS);
+        assert_eq!(interpreter.run().unwrap(), ExecutionResult::Stop);
+        assert_eq!(interpreter.stack().data(), &[uint!("0")]);
+    }
+
+    #[test]
+    fn can_charge_gas() {
+        // PUSH1 0x02, PUSH1 0x03, MUL, PUSH1 0x08, EXP, STOP
+        let mut interpreter =
+            Interpreter::new(&[0x60, 0x02, 0x60, 0x03, 0x02, 0x60, 0x08, 0x0a, 0x00], GAS);
+        assert_eq!(interpreter.run().unwrap(), ExecutionResult::Stop);
+        // 3 x PUSH1 (3) + MUL (5) + EXP (10 + 50 for a 1-byte exponent)
+        assert_eq!(interpreter.gas().used(), 3 * 3 + 5 + 10 + 50);
+    }
+
+    #[test]
+    fn cannot_run_out_of_gas() {
+        // PUSH1 0x02, PUSH1 0x03, MUL
+        let mut interpreter = Interpreter::new(&[0x60, 0x02, 0x60, 0x03, 0x02], 10);
+        assert!(matches!(
+            interpreter.run(),
+            Err(ExecutionError::OutOfGas(_))
+        ));
+        // MUL was not executed, and all the gas is consumed
+        assert_eq!(interpreter.stack().len(), 2);
+        assert_eq!(interpreter.gas().remaining(), 0);
+    }
+
+    #[test]
+    fn can_return() {
+        // PUSH1 0x04, PUSH1 0x00, RETURN
+        let mut interpreter = Interpreter::new(&[0x60, 0x04, 0x60, 0x00, 0xf3], GAS);
+        assert_eq!(
+            interpreter.run().unwrap(),
+            ExecutionResult::Return(vec![0x00; 4])
+        );
+        // Memory grows by whole words
+        assert_eq!(interpreter.memory().len(), 32);
+    }
+
+    #[test]
+    fn can_return_stored_memory() {
+        // PUSH2 0xbeef, PUSH1 0x00, MSTORE, PUSH1 0x02, PUSH1 0x1e, RETURN
+        let mut interpreter = Interpreter::new(
+            &[
+                0x61, 0xbe, 0xef, 0x60, 0x00, 0x52, 0x60, 0x02, 0x60, 0x1e, 0xf3,
+            ],
+            GAS,
+        );
+        assert_eq!(
+            interpreter.run().unwrap(),
+            ExecutionResult::Return(vec![0xbe, 0xef])
+        );
+        // 4 x PUSH (3) + MSTORE (3) + 1 word of memory (3)
+        assert_eq!(interpreter.gas().used(), 4 * 3 + 3 + 3);
+    }
+
+    #[test]
+    fn can_resize_memory_to_whole_words() {
+        // PUSH1 0xff, PUSH1 0x21, MSTORE8, MSIZE
+        let mut interpreter = Interpreter::new(&[0x60, 0xff, 0x60, 0x21, 0x53, 0x59], GAS);
+        assert_eq!(interpreter.run().unwrap(), ExecutionResult::Stop);
+        assert_eq!(interpreter.stack().data(), &[uint!("64")]);
+        assert_eq!(interpreter.memory().as_ref()[0x21], 0xff);
+    }
+
+    #[test]
+    fn can_charge_mcopy() {
+        // PUSH1 0x20, PUSH1 0x00, PUSH1 0x20, MCOPY
+        let mut interpreter = Interpreter::new(&[0x60, 0x20, 0x60, 0x00, 0x60, 0x20, 0x5e], GAS);
+        assert_eq!(interpreter.run().unwrap(), ExecutionResult::Stop);
+        assert_eq!(interpreter.memory().len(), 64);
+        // 3 x PUSH1 (3) + MCOPY (3 + 3 per word copied) + 2 words of memory
+        assert_eq!(interpreter.gas().used(), 3 * 3 + 3 + 3 + 2 * 3);
+    }
+
+    #[test]
+    fn can_charge_memory_expansion() {
+        // PUSH1 0x20, PUSH1 0x20, RETURN
+        let mut interpreter = Interpreter::new(&[0x60, 0x20, 0x60, 0x20, 0xf3], GAS);
+        assert_eq!(
+            interpreter.run().unwrap(),
+            ExecutionResult::Return(vec![0x00; 32])
+        );
+        assert_eq!(interpreter.memory().len(), 64);
+        // 2 x PUSH1 (3) + 2 words of memory (3 each)
+        assert_eq!(interpreter.gas().used(), 2 * 3 + 2 * 3);
+    }
+
+    #[test]
+    fn can_hash_memory() {
+        // PUSH1 0x21, PUSH1 0x00, KECCAK256
+        let mut interpreter =
+            Interpreter::new(&[0x60, 0x21, 0x60, 0x00, 0x20], GAS).with_preimage_recording();
+        assert_eq!(interpreter.run().unwrap(), ExecutionResult::Stop);
+        let hash = *interpreter.stack().back(0).unwrap();
+        assert_eq!(
+            hash,
+            U256::from_be_bytes(crate::crypto::keccak256(&[0x00; 33]))
+        );
+        assert_eq!(interpreter.memory().len(), 64);
+        // 2 x PUSH1 (3) + KECCAK256 (30 + 6 per word hashed) + 2 words of memory
+        assert_eq!(interpreter.gas().used(), 2 * 3 + 30 + 2 * 6 + 2 * 3);
+        assert_eq!(
+            interpreter.preimages().unwrap().get(&hash),
+            Some(&[0x00; 33][..])
+        );
+    }
+
+    #[test]
+    fn can_sstore_and_sload() {
+        // PUSH1 0x2a, PUSH1 0x01, SSTORE, PUSH1 0x01, SLOAD
+        let mut interpreter =
+            Interpreter::new(&[0x60, 0x2a, 0x60, 0x01, 0x55, 0x60, 0x01, 0x54], GAS);
+        assert_eq!(interpreter.run().unwrap(), ExecutionResult::Stop);
+        assert_eq!(interpreter.stack().data(), &[uint!("0x2a")]);
+        assert_eq!(
+            interpreter.state().get_state(&[0; 20], &uint!("1")),
+            uint!("0x2a")
+        );
+        // 3 x PUSH1 (3) + SSTORE (2100 cold + 20000 set) + SLOAD (100 warm)
+        assert_eq!(interpreter.gas().used(), 3 * 3 + 2100 + 20000 + 100);
+    }
+
+    #[test]
+    fn can_refund_cleared_slot() {
+        let mut state = MemoryStateDB::new();
+        state.set_state(&[0; 20], uint!("1"), uint!("1"));
+        state.finalise();
+        // PUSH1 0x00, PUSH1 0x01, SSTORE
+        let code = [0x60, 0x00, 0x60, 0x01, 0x55];
+
+        let mut interpreter = Interpreter::new(&code, GAS).with_state(state.clone());
+        assert_eq!(interpreter.run().unwrap(), ExecutionResult::Stop);
+        // 2 x PUSH1 (3) + SSTORE (2100 cold + 2900 reset)
+        assert_eq!(interpreter.gas().used(), 5006);
+        assert_eq!(interpreter.state().get_refund(), 4800);
+        // The refund is capped to a fifth of the gas used since London
+        assert_eq!(interpreter.gas_refund(), 1001);
+
+        let mut interpreter = Interpreter::new(&code, GAS)
+            .with_fork(Fork::Berlin)
+            .with_state(state);
+        assert_eq!(interpreter.run().unwrap(), ExecutionResult::Stop);
+        assert_eq!(interpreter.state().get_refund(), 15000);
+        assert_eq!(interpreter.gas_refund(), 5006 / 2);
+    }
+
+    #[test]
+    fn can_warm_accounts_up() {
+        // PUSH1 0xbb, BALANCE, PUSH1 0xbb, BALANCE, PUSH1 0xcc, EXTCODESIZE
+        let code = [0x60, 0xbb, 0x31, 0x60, 0xbb, 0x31, 0x60, 0xcc, 0x3b];
+        let mut interpreter = Interpreter::new(&code, GAS);
+        assert_eq!(interpreter.run().unwrap(), ExecutionResult::Stop);
+        // 3 x PUSH1 (3) + BALANCE (2600 cold) + BALANCE (100 warm)
+        // + EXTCODESIZE (2600 cold)
+        assert_eq!(interpreter.gas().used(), 3 * 3 + 2600 + 100 + 2600);
+        let mut bb = [0; 20];
+        bb[19] = 0xbb;
+        let mut cc = [0; 20];
+        cc[19] = 0xcc;
+        assert_eq!(
+            interpreter.state().access_list().tuples(&[]),
+            vec![
+                AccessTuple {
+                    address: bb,
+                    storage_keys: vec![],
+                },
+                AccessTuple {
+                    address: cc,
+                    storage_keys: vec![],
+                },
+            ]
+        );
+
+        // Accounts in the access list of the transaction are warm already
+        let mut state = MemoryStateDB::new();
+        let tuples = [AccessTuple {
+            address: bb,
+            storage_keys: vec![],
+        }];
+        state.prepare(Fork::LATEST, &[0; 20], &[0; 20], None, &tuples);
+        let mut interpreter = Interpreter::new(&code, GAS).with_state(state);
+        assert_eq!(interpreter.run().unwrap(), ExecutionResult::Stop);
+        assert_eq!(interpreter.gas().used(), 3 * 3 + 100 + 100 + 2600);
+
+        // Before Berlin, there is no access list
+        let mut interpreter = Interpreter::new(&code, GAS).with_fork(Fork::Istanbul);
+        assert_eq!(interpreter.run().unwrap(), ExecutionResult::Stop);
+        assert_eq!(interpreter.gas().used(), 3 * 3 + 700 + 700 + 700);
+    }
+
+    #[test]
+    fn can_record_reverted_accesses() {
+        // PUSH1 0x01, SLOAD, POP, PUSH0, PUSH0, REVERT
+        let code = [0x60, 0x01, 0x54, 0x50, 0x5f, 0x5f, 0xfd];
+        let mut interpreter = Interpreter::new(&code, GAS);
+        assert_eq!(interpreter.run().unwrap(), ExecutionResult::Revert(vec![]));
+        // The slot is cold again, but it still has to be in the access list of
+        // the transaction
+        assert_eq!(
+            interpreter
+                .state()
+                .slot_in_access_list(&[0; 20], &uint!("1")),
+            (false, false)
+        );
+        assert_eq!(
+            interpreter.state().accessed().tuples(&[]),
+            vec![AccessTuple {
+                address: [0; 20],
+                storage_keys: vec![uint!("1")],
+            }]
+        );
+    }
+
+    #[test]
+    fn can_revert_state() {
+        // PUSH1 0x01, PUSH1 0x01, SSTORE, PUSH1 0x00, PUSH1 0x00, REVERT
+        let code = [0x60, 0x01, 0x60, 0x01, 0x55, 0x60, 0x00, 0x60, 0x00, 0xfd];
+        let mut interpreter = Interpreter::new(&code, GAS);
+        assert_eq!(interpreter.run().unwrap(), ExecutionResult::Revert(vec![]));
+        assert_eq!(interpreter.state().get_state(&[0; 20], &uint!("1")), 0);
+        assert_eq!(interpreter.state().get_refund(), 0);
+    }
+
+    #[test]
+    fn cannot_keep_refund_of_failed_sstore() {
+        let mut state = MemoryStateDB::new();
+        state.set_state(&[0; 20], uint!("1"), uint!("1"));
+        state.finalise();
+        // PUSH1 0x00, PUSH1 0x01, SSTORE, with enough gas for the PUSH1s but
+        // not for the SSTORE (2100 cold + 2900 reset)
+        let mut interpreter =
+            Interpreter::new(&[0x60, 0x00, 0x60, 0x01, 0x55], 6 + 4999).with_state(state);
+        assert!(matches!(
+            interpreter.run(),
+            Err(ExecutionError::OutOfGas(_))
+        ));
+        assert_eq!(interpreter.state().get_refund(), 0);
+        assert_eq!(interpreter.gas_refund(), 0);
+        assert_eq!(
+            interpreter.state().get_state(&[0; 20], &uint!("1")),
+            uint!("1")
+        );
+        assert_eq!(
+            interpreter
+                .state()
+                .slot_in_access_list(&[0; 20], &uint!("1")),
+            (false, false)
+        );
+    }
+
+    #[test]
+    fn can_tstore_and_tload() {
+        // PUSH1 0x2a, PUSH1 0x01, TSTORE, PUSH1 0x01, TLOAD
+        let mut interpreter =
+            Interpreter::new(&[0x60, 0x2a, 0x60, 0x01, 0x5d, 0x60, 0x01, 0x5c], GAS);
+        assert_eq!(interpreter.run().unwrap(), ExecutionResult::Stop);
+        assert_eq!(interpreter.stack().data(), &[uint!("0x2a")]);
+        // 3 x PUSH1 (3) + TSTORE (100) + TLOAD (100)
+        assert_eq!(interpreter.gas().used(), 3 * 3 + 100 + 100);
+        // Transient storage is gone at the end of the transaction
+        interpreter.state_mut().finalise();
+        assert_eq!(
+            interpreter
+                .state()
+                .get_transient_state(&[0; 20], &uint!("1")),
+            U256::ZERO
+        );
+    }
+
+    #[test]
+    fn can_revert_transient_storage() {
+        // PUSH1 0x2a, PUSH1 0x01, TSTORE, PUSH1 0x00, PUSH1 0x00, REVERT
+        let code = [0x60, 0x2a, 0x60, 0x01, 0x5d, 0x60, 0x00, 0x60, 0x00, 0xfd];
+        let mut interpreter = Interpreter::new(&code, GAS);
+        assert_eq!(interpreter.run().unwrap(), ExecutionResult::Revert(vec![]));
+        assert_eq!(
+            interpreter
+                .state()
+                .get_transient_state(&[0; 20], &uint!("1")),
+            U256::ZERO
+        );
+    }
+
+    #[test]
+    fn cannot_write_in_static_context() {
+        // PUSH1 0x01, PUSH1 0x01, TSTORE
+        let code = [0x60, 0x01, 0x60, 0x01, 0x5d];
+        let mut interpreter = Interpreter::new(&code, GAS).with_read_only();
+        assert!(matches!(
+            interpreter.run(),
+            Err(ExecutionError::WriteProtection)
+        ));
+        assert_eq!(interpreter.gas().remaining(), 0);
+
+        // PUSH1 0x01, PUSH1 0x01, SSTORE
+        let code = [0x60, 0x01, 0x60, 0x01, 0x55];
+        let mut interpreter = Interpreter::new(&code, GAS).with_read_only();
+        assert!(matches!(
+            interpreter.run(),
+            Err(ExecutionError::WriteProtection)
+        ));
+
+        // Reading is fine: PUSH1 0x01, TLOAD, PUSH1 0x01, SLOAD
+        let code = [0x60, 0x01, 0x5c, 0x60, 0x01, 0x54];
+        let mut interpreter = Interpreter::new(&code, GAS).with_read_only();
+        assert_eq!(interpreter.run().unwrap(), ExecutionResult::Stop);
+    }
+
+    #[test]
+    fn cannot_sstore_within_stipend() {
+        // PUSH1 0x01, PUSH1 0x01, SSTORE
+        let mut interpreter = Interpreter::new(&[0x60, 0x01, 0x60, 0x01, 0x55], 2306);
+        assert!(matches!(
+            interpreter.run(),
+            Err(ExecutionError::ReentrancySentry(_))
+        ));
+        assert_eq!(interpreter.state().get_state(&[0; 20], &uint!("1")), 0);
+    }
+
+    #[test]
+    fn cannot_pay_for_huge_memory_expansion() {
+        // PUSH1 0x01, PUSH5 0x1000000000, RETURN
+        let mut interpreter =
+            Interpreter::new(&[0x60, 0x01, 0x64, 0x10, 0x00, 0x00, 0x00, 0x00, 0xf3], GAS);
+        assert!(matches!(
+            interpreter.run(),
+            Err(ExecutionError::OutOfGas(_))
+        ));
+        assert_eq!(interpreter.memory().len(), 0);
+    }
+
+    #[test]
+    fn can_revert() {
+        // PUSH1 0x00, PUSH1 0x00, REVERT
+        let mut interpreter = Interpreter::new(&[0x60, 0x00, 0x60, 0x00, 0xfd], GAS);
+        assert_eq!(interpreter.run().unwrap(), ExecutionResult::Revert(vec![]));
+        // Unlike an error, REVERT does not consume all the gas
+        assert_eq!(interpreter.gas().used(), 6);
+    }
+
+    #[test]
+    fn can_revert_only_changes_of_run() {
+        let mut state = MemoryStateDB::new();
+        state.add_balance(&[0xaa; 20], uint!("1"));
+        // PUSH0, PUSH0, REVERT
+        let mut interpreter = Interpreter::new(&[0x5f, 0x5f, 0xfd], GAS).with_state(state);
+        assert_eq!(interpreter.run().unwrap(), ExecutionResult::Revert(vec![]));
+        // What was there before the run stays
+        assert_eq!(interpreter.state().get_balance(&[0xaa; 20]), uint!("1"));
+    }
+
+    #[test]
+    fn cannot_run_undefined_opcode() {
+        let mut interpreter = Interpreter::new(&[0x0c], GAS);
+        assert!(matches!(
+            interpreter.run(),
+            Err(ExecutionError::InvalidOpcode(_))
+        ));
+    }
+
+    #[test]
+    fn cannot_run_invalid() {
+        let mut interpreter = Interpreter::new(&[0xfe], GAS);
+        assert!(matches!(
+            interpreter.run(),
+            Err(ExecutionError::InvalidOpcode(_))
+        ));
+    }
+
+    #[test]
+    fn cannot_pop_empty_stack() {
+        let mut interpreter = Interpreter::new(&[0x50], GAS);
+        assert!(matches!(
+            interpreter.run(),
+            Err(ExecutionError::Stack(StackError::StackUnderflow {
+                required: 1,
+                available: 0
+            }))
+        ));
+    }
+
+    #[test]
+    fn cannot_overflow_stack() {
+        // 1025 x PUSH1 0x01
+        let code = [0x60, 0x01].repeat(1025);
+        let mut interpreter = Interpreter::new(&code, GAS);
+        assert!(matches!(
+            interpreter.run(),
+            Err(ExecutionError::Stack(StackError::StackOverflow {
+                len: 1025
+            }))
+        ));
+        assert_eq!(interpreter.stack().len(), 1024);
+    }
+
+    #[test]
+    fn cannot_return_with_huge_offset() {
+        // PUSH1 0x01, PUSH32 0xff..ff, RETURN
+        let mut code = vec![0x60, 0x01, 0x7f];
+        code.extend([0xff; 32]);
+        code.push(0xf3);
+        let mut interpreter = Interpreter::new(&code, GAS);
+        assert!(matches!(
+            interpreter.run(),
+            Err(ExecutionError::GasUintOverflow(_))
+        ));
+    }
+
+    #[test]
+    fn can_loop() {
+        // Count down from 3 in memory word 0:
+        // PUSH1 0x03, PUSH1 0x00, MSTORE,
+        // 0x05: JUMPDEST, PUSH1 0x01, PUSH1 0x00, MLOAD, SUB, PUSH1 0x00, MSTORE,
+        // PUSH1 0x00, MLOAD, PUSH1 0x05, JUMPI, PC
+        let code = [
+            0x60, 0x03, 0x60, 0x00, 0x52, 0x5b, 0x60, 0x01, 0x60, 0x00, 0x51, 0x03, 0x60, 0x00,
+            0x52, 0x60, 0x00, 0x51, 0x60, 0x05, 0x57, 0x58,
+        ];
+        let mut interpreter = Interpreter::new(&code, GAS);
+        assert_eq!(interpreter.run().unwrap(), ExecutionResult::Stop);
+        assert_eq!(interpreter.stack().data(), &[uint!("21")]);
+        assert_eq!(interpreter.memory().as_ref(), [0x00; 32]);
+    }
+
+    #[test]
+    fn can_jump_over_invalid() {
+        // PUSH1 0x04, JUMP, INVALID, JUMPDEST, GAS
+        let mut interpreter = Interpreter::new(&[0x60, 0x04, 0x56, 0xfe, 0x5b, 0x5a], GAS);
+        assert_eq!(interpreter.run().unwrap(), ExecutionResult::Stop);
+        // PUSH1 (3) + JUMP (8) + JUMPDEST (1) + GAS (2)
+        assert_eq!(interpreter.gas().used(), 3 + 8 + 1 + 2);
+        assert_eq!(
+            interpreter.stack().data(),
+            &[U256::from(GAS - interpreter.gas().used())]
+        );
+    }
+
+    #[test]
+    fn can_jump_to_start() {
+        // JUMPDEST, PC, ISZERO, PUSH1 0x00, JUMPI -> jumps back once, with
+        // PC pushing 0 on both runs
+        let mut interpreter = Interpreter::new(&[0x5b, 0x58, 0x15, 0x60, 0x00, 0x57], GAS);
+        assert_eq!(interpreter.run().unwrap(), ExecutionResult::Stop);
+        assert_eq!(interpreter.stack().len(), 0);
+    }
+
+    #[test]
+    fn cannot_jump_into_push_data() {
+        // PUSH1 0x01, JUMP with a JUMPDEST byte as the immediate of PUSH1
+        let mut interpreter = Interpreter::new(&[0x60, 0x5b, 0x60, 0x01, 0x56], GAS);
+        assert!(matches!(
+            interpreter.run(),
+            Err(ExecutionError::InvalidJump { dest }) if dest == 1
+        ));
+        assert_eq!(interpreter.gas().remaining(), 0);
+    }
+
+    #[test]
+    fn can_run_with_cached_analysis() {
+        let mut cache = crate::analysis::JumpDestCache::new();
+        // PUSH1 0x04, JUMP, INVALID, JUMPDEST
+        let code = [0x60, 0x04, 0x56, 0xfe, 0x5b];
+        for _ in 0..2 {
+            let analysis = cache.analysis([0x01; 32], &code);
+            let mut interpreter = Interpreter::new(&code, GAS).with_analysis(analysis);
+            assert_eq!(interpreter.run().unwrap(), ExecutionResult::Stop);
+        }
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn can_push_truncated_immediate() {
+        // PUSH0, PUSH4 0x0102 with the code ending in the middle of it
+        let mut interpreter = Interpreter::new(&[0x5f, 0x63, 0x01, 0x02], GAS);
+        assert_eq!(interpreter.run().unwrap(), ExecutionResult::Stop);
+        assert_eq!(
+            interpreter.stack().data(),
+            &[uint!("0"), uint!("0x01020000")]
+        );
+        // PUSH0 (2) + PUSH4 (3)
+        assert_eq!(interpreter.gas().used(), 2 + 3);
+    }
+
+    #[test]
+    fn can_loop_on_the_stack() {
+        // Sum 3 + 2 + 1 with a counter kept on the stack:
+        // PUSH0, PUSH1 0x03,
+        // 0x03: JUMPDEST, SWAP1, DUP2, ADD, SWAP1, PUSH1 0x01, SWAP1, SUB,
+        // DUP1, PUSH1 0x03, JUMPI
+        let code = [
+            0x5f, 0x60, 0x03, 0x5b, 0x90, 0x81, 0x01, 0x90, 0x60, 0x01, 0x90, 0x03, 0x80, 0x60,
+            0x03, 0x57,
+        ];
+        let mut interpreter = Interpreter::new(&code, GAS);
+        assert_eq!(interpreter.run().unwrap(), ExecutionResult::Stop);
+        assert_eq!(interpreter.stack().data(), &[uint!("6"), uint!("0")]);
+    }
+
+    #[test]
+    fn cannot_dup_or_swap_past_the_bottom() {
+        // PUSH1 0x01, DUP2
+        let mut interpreter = Interpreter::new(&[0x60, 0x01, 0x81], GAS);
+        assert!(matches!(
+            interpreter.run(),
+            Err(ExecutionError::Stack(StackError::StackUnderflow { .. }))
+        ));
+        // PUSH1 0x01, SWAP1
+        let mut interpreter = Interpreter::new(&[0x60, 0x01, 0x90], GAS);
+        assert!(matches!(
+            interpreter.run(),
+            Err(ExecutionError::Stack(StackError::StackUnderflow { .. }))
+        ));
+    }
+
+    #[test]
+    fn cannot_run_opcodes_before_their_fork() {
+        // PUSH0
+        let mut interpreter = Interpreter::new(&[0x5f], GAS).with_fork(Fork::Paris);
+        assert!(matches!(
+            interpreter.run(),
+            Err(ExecutionError::InvalidOpcode(_))
+        ));
+        assert_eq!(interpreter.gas().remaining(), 0);
+
+        // PUSH1 0x01, PUSH1 0x01, SHL
+        let code = [0x60, 0x01, 0x60, 0x01, 0x1b];
+        let mut interpreter = Interpreter::new(&code, GAS).with_fork(Fork::Byzantium);
+        assert!(matches!(
+            interpreter.run(),
+            Err(ExecutionError::InvalidOpcode(_))
+        ));
+        let mut interpreter = Interpreter::new(&code, GAS).with_fork(Fork::Constantinople);
+        assert_eq!(interpreter.run().unwrap(), ExecutionResult::Stop);
+        assert_eq!(interpreter.stack().data(), &[uint!("2")]);
+    }
+
+    #[test]
+    fn can_charge_gas_of_fork() {
+        // PUSH2 0x0100, PUSH1 0x02, EXP
+        let code = [0x61, 0x01, 0x00, 0x60, 0x02, 0x0a];
+        let mut interpreter = Interpreter::new(&code, GAS).with_fork(Fork::Homestead);
+        assert_eq!(interpreter.fork(), Fork::Homestead);
+        interpreter.run().unwrap();
+        // 2 x PUSH (3) + EXP (10 + 10 per exponent byte)
+        assert_eq!(interpreter.gas().used(), 2 * 3 + 10 + 2 * 10);
+
+        let mut interpreter = Interpreter::new(&code, GAS);
+        assert_eq!(interpreter.fork(), Fork::LATEST);
+        interpreter.run().unwrap();
+        assert_eq!(interpreter.gas().used(), 2 * 3 + 10 + 2 * 50);
+    }
+
+    #[test]
+    fn can_read_call_context() {
+        let contract = Contract {
+            caller: [0x11; 20],
+            address: [0x22; 20],
+            value: uint!("7"),
+            input: vec![0xaa, 0xbb],
+        };
+        // CALLVALUE, PUSH1 0x02, PUSH0, PUSH0, CALLDATACOPY, PUSH1 0x02, PUSH0,
+        // RETURN
+        let code = [0x34, 0x60, 0x02, 0x5f, 0x5f, 0x37, 0x60, 0x02, 0x5f, 0xf3];
+        let mut interpreter = Interpreter::new(&code, GAS).with_contract(contract);
+        assert_eq!(
+            interpreter.run().unwrap(),
+            ExecutionResult::Return(vec![0xaa, 0xbb])
+        );
+        assert_eq!(interpreter.stack().data(), &[uint!("7")]);
+        // CALLVALUE (2) + 2 x PUSH1 (3) + 3 x PUSH0 (2) + CALLDATACOPY (3 + 3
+        // per word) + 1 word of memory (3)
+        assert_eq!(interpreter.gas().used(), 2 + 2 * 3 + 3 * 2 + 3 + 3 + 3);
+    }
+
+    #[test]
+    fn can_step() {
+        // PUSH1 0x01, STOP
+        let mut interpreter = Interpreter::new(&[0x60, 0x01, 0x00], GAS);
+        assert_eq!(interpreter.step().unwrap(), None);
+        assert_eq!(interpreter.pc(), 2);
+        assert_eq!(interpreter.step().unwrap(), Some(ExecutionResult::Stop));
+    }
+}
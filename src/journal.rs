@@ -0,0 +1,211 @@
+use ethnum::U256;
+
+use crate::{contract::Address, state::Account};
+
+/// JournalEntry is a single change to the world state, holding what is needed
+/// to undo it. It is the counterpart of geth's `journalEntry`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum JournalEntry {
+    /// An account was created, replacing `prev` if it already existed
+    CreateAccount {
+        address: Address,
+        prev: Option<Box<Account>>,
+    },
+    /// The balance of an account changed from `prev`
+    Balance { address: Address, prev: U256 },
+    /// The nonce of an account changed from `prev`
+    Nonce { address: Address, prev: u64 },
+    /// The code of an account changed from `prev`
+    Code { address: Address, prev: Vec<u8> },
+    /// A storage slot changed from `prev`
+    Storage {
+        address: Address,
+        key: U256,
+        prev: U256,
+    },
+    /// An account was self-destructed. `prev` is whether it already was, and
+    /// `prev_balance` is the balance it held.
+    SelfDestruct {
+        address: Address,
+        prev: bool,
+        prev_balance: U256,
+    },
+    /// A transient storage slot changed from `prev` (EIP-1153)
+    TransientStorage {
+        address: Address,
+        key: U256,
+        prev: U256,
+    },
+    /// The refund counter changed from `prev`
+    Refund { prev: u64 },
+    /// An account was added to the access list
+    AccessListAddAccount { address: Address },
+    /// A storage slot was added to the access list
+    AccessListAddSlot { address: Address, key: U256 },
+}
+
+impl JournalEntry {
+    /// Address returns the account the change applies to, or None if it does
+    /// not apply to an account (e.g. the refund counter).
+    pub fn address(&self) -> Option<&Address> {
+        match self {
+            JournalEntry::CreateAccount { address, .. }
+            | JournalEntry::Balance { address, .. }
+            | JournalEntry::Nonce { address, .. }
+            | JournalEntry::Code { address, .. }
+            | JournalEntry::Storage { address, .. }
+            | JournalEntry::TransientStorage { address, .. }
+            | JournalEntry::SelfDestruct { address, .. }
+            | JournalEntry::AccessListAddAccount { address }
+            | JournalEntry::AccessListAddSlot { address, .. } => Some(address),
+            JournalEntry::Refund { .. } => None,
+        }
+    }
+}
+
+/// Journal records the changes to the world state, in order, so that they can
+/// be undone back to a snapshot. It is the counterpart of geth's `journal`.
+///
+/// A snapshot is taken when a call frame starts, and reverted to if the frame
+/// fails or hits REVERT. The journal does not know about the state itself: it
+/// hands back the entries to undo, and the state applies them.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Journal {
+    entries: Vec<JournalEntry>,
+    /// The snapshots which can be reverted to, as their id and the number of
+    /// entries when they were taken. The ids are increasing.
+    revisions: Vec<(usize, usize)>,
+    next_revision_id: usize,
+}
+
+impl Journal {
+    pub fn new() -> Self {
+        Journal::default()
+    }
+
+    /// Append records a change.
+    pub fn append(&mut self, entry: JournalEntry) {
+        self.entries.push(entry);
+    }
+
+    /// Snapshot returns the id of the current revision of the state, which
+    /// can be reverted to with [`Journal::revert_to_snapshot`].
+    pub fn snapshot(&mut self) -> usize {
+        let id = self.next_revision_id;
+        self.next_revision_id += 1;
+        self.revisions.push((id, self.entries.len()));
+        id
+    }
+
+    /// RevertToSnapshot removes the changes made since the snapshot `id` was
+    /// taken, and returns them, the most recent first, so that they can be
+    /// undone in that order. The snapshots taken after `id`, and `id` itself,
+    /// cannot be reverted to anymore.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is not a snapshot which can be reverted to, like geth
+    /// does, since this is a bug of the caller.
+    pub fn revert_to_snapshot(&mut self, id: usize) -> impl Iterator<Item = JournalEntry> + '_ {
+        let index = match self.revisions.binary_search_by_key(&id, |(id, _)| *id) {
+            Ok(index) => index,
+            Err(_) => panic!("revision id {id} cannot be reverted"),
+        };
+        let (_, len) = self.revisions[index];
+        self.revisions.truncate(index);
+        self.entries.drain(len..).rev()
+    }
+
+    /// Reset forgets all the changes and snapshots, e.g. at the end of a
+    /// transaction.
+    pub fn reset(&mut self) {
+        self.entries.clear();
+        self.revisions.clear();
+    }
+
+    /// Get the changes recorded so far, the oldest first, e.g. to compute a
+    /// state diff
+    pub fn entries(&self) -> &[JournalEntry] {
+        &self.entries
+    }
+
+    /// Get the number of changes recorded so far
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if no change was recorded
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ethnum::uint;
+
+    use super::*;
+
+    const ALICE: Address = [0xaa; 20];
+
+    fn balance(prev: u8) -> JournalEntry {
+        JournalEntry::Balance {
+            address: ALICE,
+            prev: U256::from(prev),
+        }
+    }
+
+    #[test]
+    fn can_revert_to_snapshot() {
+        let mut journal = Journal::new();
+        journal.append(balance(0));
+        let outer = journal.snapshot();
+        journal.append(balance(1));
+        let inner = journal.snapshot();
+        journal.append(balance(2));
+        journal.append(balance(3));
+
+        let undone: Vec<_> = journal.revert_to_snapshot(inner).collect();
+        assert_eq!(undone, vec![balance(3), balance(2)]);
+        assert_eq!(journal.len(), 2);
+
+        let undone: Vec<_> = journal.revert_to_snapshot(outer).collect();
+        assert_eq!(undone, vec![balance(1)]);
+        assert_eq!(journal.entries(), &[balance(0)]);
+    }
+
+    #[test]
+    fn can_revert_to_outer_snapshot() {
+        let mut journal = Journal::new();
+        let outer = journal.snapshot();
+        journal.append(balance(1));
+        journal.snapshot();
+        journal.append(balance(2));
+        assert_eq!(journal.revert_to_snapshot(outer).count(), 2);
+        assert!(journal.is_empty());
+        // Ids are never reused
+        assert_eq!(journal.snapshot(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "revision id 1 cannot be reverted")]
+    fn cannot_revert_to_reverted_snapshot() {
+        let mut journal = Journal::new();
+        let outer = journal.snapshot();
+        let inner = journal.snapshot();
+        assert_eq!(inner, 1);
+        journal.revert_to_snapshot(outer).count();
+        journal.revert_to_snapshot(inner).count();
+    }
+
+    #[test]
+    fn can_get_entry_address() {
+        let entry = JournalEntry::Storage {
+            address: ALICE,
+            key: uint!("1"),
+            prev: uint!("2"),
+        };
+        assert_eq!(entry.address(), Some(&ALICE));
+        assert_eq!(JournalEntry::Refund { prev: 0 }.address(), None);
+    }
+}
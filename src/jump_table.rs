@@ -0,0 +1,365 @@
+use crate::{
+    fork::Fork,
+    gas::{
+        BALANCE_GAS_EIP150, BALANCE_GAS_EIP1884, BALANCE_GAS_FRONTIER, CALL_GAS_EIP150,
+        CALL_GAS_FRONTIER, CREATE_GAS, EXTCODE_COPY_BASE_EIP150, EXTCODE_COPY_BASE_FRONTIER,
+        EXTCODE_HASH_GAS_CONSTANTINOPLE, EXTCODE_HASH_GAS_EIP1884, EXTCODE_SIZE_GAS_EIP150,
+        EXTCODE_SIZE_GAS_FRONTIER, JUMPDEST_GAS, KECCAK256_GAS, SELFDESTRUCT_GAS, SLOAD_GAS_EIP150,
+        SLOAD_GAS_EIP1884, SLOAD_GAS_FRONTIER, WARM_STORAGE_READ_COST,
+    },
+    opcodes::{InvalidOpcode, Opcode},
+};
+
+/// Operation is an entry of the jump table: an opcode available in the fork,
+/// with its constant cost in that fork.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Operation {
+    pub opcode: Opcode,
+    /// The gas charged before the opcode runs. Some opcodes also have a
+    /// dynamic cost charged on top of it.
+    pub constant_gas: u64,
+}
+
+/// JumpTable holds the instruction set of a fork: which bytes are valid
+/// opcodes, and what they cost. It is the counterpart of geth's `JumpTable`,
+/// and is built the same way: each fork starts from the table of the previous
+/// one, enables the opcodes it introduced and applies its repricings.
+#[derive(Clone, Debug)]
+pub struct JumpTable {
+    fork: Fork,
+    operations: [Option<Operation>; 256],
+}
+
+impl JumpTable {
+    /// Create the instruction set of `fork`.
+    pub fn new(fork: Fork) -> Self {
+        match fork {
+            Fork::Frontier => new_frontier_instruction_set(),
+            Fork::Homestead => new_homestead_instruction_set(),
+            Fork::TangerineWhistle => new_tangerine_whistle_instruction_set(),
+            Fork::SpuriousDragon => new_spurious_dragon_instruction_set(),
+            Fork::Byzantium => new_byzantium_instruction_set(),
+            Fork::Constantinople => new_constantinople_instruction_set(),
+            Fork::Petersburg => new_petersburg_instruction_set(),
+            Fork::Istanbul => new_istanbul_instruction_set(),
+            Fork::Berlin => new_berlin_instruction_set(),
+            Fork::London => new_london_instruction_set(),
+            Fork::Paris => new_paris_instruction_set(),
+            Fork::Shanghai => new_shanghai_instruction_set(),
+            Fork::Cancun => new_cancun_instruction_set(),
+            Fork::Prague => new_prague_instruction_set(),
+        }
+    }
+
+    /// Get the fork of the instruction set
+    pub fn fork(&self) -> Fork {
+        self.fork
+    }
+
+    /// Operation returns the entry of `byte`, failing if it is not a valid
+    /// opcode in the fork (e.g. PUSH0 before Shanghai).
+    pub fn operation(&self, byte: u8) -> Result<&Operation, InvalidOpcode> {
+        self.operations[byte as usize]
+            .as_ref()
+            .ok_or_else(|| InvalidOpcode::new(byte))
+    }
+
+    /// Decode returns the opcode of `byte`, failing if it is not a valid
+    /// opcode in the fork.
+    pub fn decode(&self, byte: u8) -> Result<Opcode, InvalidOpcode> {
+        Ok(self.operation(byte)?.opcode)
+    }
+
+    /// ConstantGas returns the constant cost of `op` in the fork, which is 0
+    /// if the opcode is not available in the fork.
+    pub fn constant_gas(&self, op: &Opcode) -> u64 {
+        self.operations[op.byte() as usize].map_or(0, |operation| operation.constant_gas)
+    }
+
+    /// Activate moves the table to `fork`, and enables the opcodes introduced
+    /// by `fork` at the cost of their gas tier. Opcodes with a special cost
+    /// are enabled for free, and must be priced with [`JumpTable::set_gas`].
+    fn activate(&mut self, fork: Fork) {
+        self.fork = fork;
+        for byte in 0..=255 {
+            let Ok(opcode) = Opcode::try_from(byte) else {
+                continue;
+            };
+            let info = opcode.info();
+            if info.fork == fork {
+                self.operations[byte as usize] = Some(Operation {
+                    opcode,
+                    constant_gas: info.gas_tier.gas().unwrap_or(0),
+                });
+            }
+        }
+    }
+
+    /// SetGas changes the constant cost of `ops`, which must be enabled.
+    fn set_gas(&mut self, ops: &[Opcode], gas: u64) {
+        for op in ops {
+            let operation = self.operations[op.byte() as usize]
+                .as_mut()
+                .expect("opcode is enabled");
+            operation.constant_gas = gas;
+        }
+    }
+}
+
+#[cfg(not(tarpaulin_include))]
+impl Default for JumpTable {
+    fn default() -> Self {
+        Self::new(Fork::LATEST)
+    }
+}
+
+const LOGS: [Opcode; 5] = [
+    Opcode::LOG0,
+    Opcode::LOG1,
+    Opcode::LOG2,
+    Opcode::LOG3,
+    Opcode::LOG4,
+];
+
+fn new_frontier_instruction_set() -> JumpTable {
+    let mut table = JumpTable {
+        fork: Fork::Frontier,
+        operations: [None; 256],
+    };
+    table.activate(Fork::Frontier);
+    table.set_gas(&[Opcode::KECCAK256], KECCAK256_GAS);
+    table.set_gas(&[Opcode::JUMPDEST], JUMPDEST_GAS);
+    table.set_gas(&[Opcode::BALANCE], BALANCE_GAS_FRONTIER);
+    table.set_gas(&[Opcode::EXTCODESIZE], EXTCODE_SIZE_GAS_FRONTIER);
+    table.set_gas(&[Opcode::EXTCODECOPY], EXTCODE_COPY_BASE_FRONTIER);
+    table.set_gas(&[Opcode::SLOAD], SLOAD_GAS_FRONTIER);
+    table.set_gas(&[Opcode::CALL, Opcode::CALLCODE], CALL_GAS_FRONTIER);
+    table.set_gas(&[Opcode::CREATE], CREATE_GAS);
+    // SSTORE, LOGn and SELFDESTRUCT are fully charged dynamically.
+    table.set_gas(&[Opcode::SSTORE, Opcode::SELFDESTRUCT], 0);
+    table.set_gas(&LOGS, 0);
+    table
+}
+
+/// Homestead adds DELEGATECALL (EIP-7).
+fn new_homestead_instruction_set() -> JumpTable {
+    let mut table = new_frontier_instruction_set();
+    table.activate(Fork::Homestead);
+    table.set_gas(&[Opcode::DELEGATECALL], CALL_GAS_FRONTIER);
+    table
+}
+
+/// Tangerine Whistle reprices the opcodes accessing the state (EIP-150).
+fn new_tangerine_whistle_instruction_set() -> JumpTable {
+    let mut table = new_homestead_instruction_set();
+    table.activate(Fork::TangerineWhistle);
+    table.set_gas(&[Opcode::BALANCE], BALANCE_GAS_EIP150);
+    table.set_gas(&[Opcode::EXTCODESIZE], EXTCODE_SIZE_GAS_EIP150);
+    table.set_gas(&[Opcode::EXTCODECOPY], EXTCODE_COPY_BASE_EIP150);
+    table.set_gas(&[Opcode::SLOAD], SLOAD_GAS_EIP150);
+    table.set_gas(
+        &[Opcode::CALL, Opcode::CALLCODE, Opcode::DELEGATECALL],
+        CALL_GAS_EIP150,
+    );
+    table.set_gas(&[Opcode::SELFDESTRUCT], SELFDESTRUCT_GAS);
+    table
+}
+
+/// Spurious Dragon only reprices EXP, which is a dynamic cost (EIP-160).
+fn new_spurious_dragon_instruction_set() -> JumpTable {
+    let mut table = new_tangerine_whistle_instruction_set();
+    table.activate(Fork::SpuriousDragon);
+    table
+}
+
+/// Byzantium adds RETURNDATASIZE, RETURNDATACOPY (EIP-211), STATICCALL
+/// (EIP-214) and REVERT (EIP-140).
+fn new_byzantium_instruction_set() -> JumpTable {
+    let mut table = new_spurious_dragon_instruction_set();
+    table.activate(Fork::Byzantium);
+    table.set_gas(&[Opcode::STATICCALL], CALL_GAS_EIP150);
+    table
+}
+
+/// Constantinople adds SHL, SHR, SAR (EIP-145), EXTCODEHASH (EIP-1052) and
+/// CREATE2 (EIP-1014).
+fn new_constantinople_instruction_set() -> JumpTable {
+    let mut table = new_byzantium_instruction_set();
+    table.activate(Fork::Constantinople);
+    table.set_gas(&[Opcode::EXTCODEHASH], EXTCODE_HASH_GAS_CONSTANTINOPLE);
+    table.set_gas(&[Opcode::CREATE2], CREATE_GAS);
+    table
+}
+
+/// Petersburg only changes the dynamic cost of SSTORE back (EIP-1283 was
+/// removed).
+fn new_petersburg_instruction_set() -> JumpTable {
+    let mut table = new_constantinople_instruction_set();
+    table.activate(Fork::Petersburg);
+    table
+}
+
+/// Istanbul adds CHAINID (EIP-1344) and SELFBALANCE, and reprices the opcodes
+/// reading the state (EIP-1884).
+fn new_istanbul_instruction_set() -> JumpTable {
+    let mut table = new_petersburg_instruction_set();
+    table.activate(Fork::Istanbul);
+    table.set_gas(&[Opcode::BALANCE], BALANCE_GAS_EIP1884);
+    table.set_gas(&[Opcode::EXTCODEHASH], EXTCODE_HASH_GAS_EIP1884);
+    table.set_gas(&[Opcode::SLOAD], SLOAD_GAS_EIP1884);
+    table
+}
+
+/// Berlin charges the access to accounts and storage depending on whether
+/// they were already accessed (EIP-2929). The warm cost is charged upfront,
+/// and the cold surcharge dynamically. SLOAD is fully charged dynamically.
+fn new_berlin_instruction_set() -> JumpTable {
+    let mut table = new_istanbul_instruction_set();
+    table.activate(Fork::Berlin);
+    table.set_gas(
+        &[
+            Opcode::BALANCE,
+            Opcode::EXTCODESIZE,
+            Opcode::EXTCODECOPY,
+            Opcode::EXTCODEHASH,
+            Opcode::CALL,
+            Opcode::CALLCODE,
+            Opcode::DELEGATECALL,
+            Opcode::STATICCALL,
+        ],
+        WARM_STORAGE_READ_COST,
+    );
+    table.set_gas(&[Opcode::SLOAD], 0);
+    table
+}
+
+/// London adds BASEFEE (EIP-3198).
+fn new_london_instruction_set() -> JumpTable {
+    let mut table = new_berlin_instruction_set();
+    table.activate(Fork::London);
+    table
+}
+
+/// Paris turns DIFFICULTY into PREVRANDAO (EIP-4399), at the same cost.
+fn new_paris_instruction_set() -> JumpTable {
+    let mut table = new_london_instruction_set();
+    table.activate(Fork::Paris);
+    table
+}
+
+/// Shanghai adds PUSH0 (EIP-3855).
+fn new_shanghai_instruction_set() -> JumpTable {
+    let mut table = new_paris_instruction_set();
+    table.activate(Fork::Shanghai);
+    table
+}
+
+/// Cancun adds TLOAD, TSTORE (EIP-1153), MCOPY (EIP-5656), BLOBHASH
+/// (EIP-4844) and BLOBBASEFEE (EIP-7516).
+fn new_cancun_instruction_set() -> JumpTable {
+    let mut table = new_shanghai_instruction_set();
+    table.activate(Fork::Cancun);
+    table.set_gas(&[Opcode::TLOAD, Opcode::TSTORE], WARM_STORAGE_READ_COST);
+    table
+}
+
+/// Prague does not change the instruction set.
+fn new_prague_instruction_set() -> JumpTable {
+    let mut table = new_cancun_instruction_set();
+    table.activate(Fork::Prague);
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_get_constant_gas() {
+        let table = JumpTable::new(Fork::Cancun);
+        assert_eq!(table.constant_gas(&Opcode::STOP), 0);
+        assert_eq!(table.constant_gas(&Opcode::ADD), 3);
+        assert_eq!(table.constant_gas(&Opcode::MUL), 5);
+        assert_eq!(table.constant_gas(&Opcode::ADDMOD), 8);
+        assert_eq!(table.constant_gas(&Opcode::EXP), 10);
+        assert_eq!(table.constant_gas(&Opcode::BLOCKHASH), 20);
+        assert_eq!(table.constant_gas(&Opcode::KECCAK256), 30);
+        assert_eq!(table.constant_gas(&Opcode::JUMPDEST), 1);
+        assert_eq!(table.constant_gas(&Opcode::PUSH0), 2);
+        assert_eq!(table.constant_gas(&Opcode::PUSH1), 3);
+        assert_eq!(table.constant_gas(&Opcode::PUSH32), 3);
+        assert_eq!(table.constant_gas(&Opcode::DUP1), 3);
+        assert_eq!(table.constant_gas(&Opcode::SWAP16), 3);
+        assert_eq!(table.constant_gas(&Opcode::BALANCE), 100);
+        assert_eq!(table.constant_gas(&Opcode::TSTORE), 100);
+        assert_eq!(table.constant_gas(&Opcode::CREATE), 32000);
+        assert_eq!(table.constant_gas(&Opcode::SELFDESTRUCT), 5000);
+        assert_eq!(table.constant_gas(&Opcode::INVALID), 0);
+    }
+
+    #[test]
+    fn can_reprice_across_forks() {
+        let gas = |fork, op| JumpTable::new(fork).constant_gas(&op);
+        assert_eq!(gas(Fork::Homestead, Opcode::BALANCE), 20);
+        assert_eq!(gas(Fork::TangerineWhistle, Opcode::BALANCE), 400);
+        assert_eq!(gas(Fork::Istanbul, Opcode::BALANCE), 700);
+        assert_eq!(gas(Fork::Berlin, Opcode::BALANCE), 100);
+
+        assert_eq!(gas(Fork::Frontier, Opcode::SLOAD), 50);
+        assert_eq!(gas(Fork::TangerineWhistle, Opcode::SLOAD), 200);
+        assert_eq!(gas(Fork::Istanbul, Opcode::SLOAD), 800);
+        assert_eq!(gas(Fork::Berlin, Opcode::SLOAD), 0);
+
+        assert_eq!(gas(Fork::Homestead, Opcode::DELEGATECALL), 40);
+        assert_eq!(gas(Fork::TangerineWhistle, Opcode::DELEGATECALL), 700);
+        assert_eq!(gas(Fork::Frontier, Opcode::SELFDESTRUCT), 0);
+        assert_eq!(gas(Fork::TangerineWhistle, Opcode::SELFDESTRUCT), 5000);
+        assert_eq!(gas(Fork::Constantinople, Opcode::EXTCODEHASH), 400);
+        assert_eq!(gas(Fork::Istanbul, Opcode::EXTCODEHASH), 700);
+    }
+
+    #[test]
+    fn can_enable_opcodes_by_fork() {
+        let frontier = JumpTable::new(Fork::Frontier);
+        assert!(frontier.decode(0x01).is_ok());
+        assert!(frontier.decode(0xf4).is_err());
+        assert!(JumpTable::new(Fork::Homestead).decode(0xf4).is_ok());
+
+        assert!(JumpTable::new(Fork::Byzantium).decode(0x1b).is_err());
+        assert_eq!(
+            JumpTable::new(Fork::Constantinople).decode(0x1b).unwrap(),
+            Opcode::SHL
+        );
+
+        assert!(JumpTable::new(Fork::Paris).decode(0x5f).is_err());
+        assert_eq!(
+            JumpTable::new(Fork::Shanghai).decode(0x5f).unwrap(),
+            Opcode::PUSH0
+        );
+
+        let shanghai = JumpTable::new(Fork::Shanghai);
+        let cancun = JumpTable::new(Fork::Cancun);
+        for op in [
+            Opcode::TLOAD,
+            Opcode::TSTORE,
+            Opcode::MCOPY,
+            Opcode::BLOBHASH,
+        ] {
+            assert!(shanghai.decode(op.byte()).is_err());
+            assert_eq!(cancun.decode(op.byte()).unwrap(), op);
+        }
+    }
+
+    #[test]
+    fn can_enable_every_opcode_in_latest_fork() {
+        let table = JumpTable::new(Fork::LATEST);
+        assert_eq!(table.fork(), Fork::LATEST);
+        for byte in 0..=255 {
+            assert_eq!(
+                table.decode(byte).is_ok(),
+                Opcode::try_from(byte).is_ok(),
+                "{byte:#04x}"
+            );
+        }
+    }
+}
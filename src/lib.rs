@@ -1,5 +1,19 @@
 #![feature(exclusive_range_pattern)]
 
+pub mod access_list;
+pub mod analysis;
+pub mod asm;
+pub mod contract;
+pub mod crypto;
+pub mod disasm;
+pub mod fork;
+pub mod gas;
+pub mod instructions;
+pub mod interpreter;
+pub mod journal;
+pub mod jump_table;
 pub mod memory;
 pub mod opcodes;
 pub mod stack;
+pub mod state;
+pub mod tracing;
@@ -1,12 +1,44 @@
 use std::usize;
 
 use ethnum::U256;
+use thiserror::Error;
+
+use crate::gas::{to_word_size, GasUintOverflow, MAX_MEMORY_SIZE, MEMORY_GAS, QUAD_COEFF_DIV};
+
+/// Errors raised by the fallible accessors of [`Memory`].
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum MemoryError {
+    /// The accessed range goes past the end of the memory. The memory should
+    /// have been resized before accessing it.
+    #[error("invalid memory: store size is {len} but {size} bytes were accessed from {offset}")]
+    OutOfBounds {
+        offset: usize,
+        size: usize,
+        len: usize,
+    },
+    /// The offset popped from the stack does not fit in a u64.
+    #[error("invalid memory: offset {offset} does not fit in 64 bits")]
+    OffsetOverflow { offset: U256 },
+    /// The value to write does not have the size of the written range.
+    #[error("invalid memory: failed to write {actual} bytes to a range of {expected} bytes")]
+    SizeMismatch { expected: usize, actual: usize },
+}
+
+/// Offset converts a word popped from the stack into a memory offset, failing
+/// if it does not fit in a u64.
+pub fn to_offset(offset: U256) -> Result<usize, MemoryError> {
+    u64::try_from(offset)
+        .ok()
+        .and_then(|offset| usize::try_from(offset).ok())
+        .ok_or(MemoryError::OffsetOverflow { offset })
+}
 
 /// The EVM has a volatile space called memory which is used to store data
 /// during execution. This memory is organized into 32-byte words.
 pub struct Memory {
     store: Vec<u8>,
-    #[allow(dead_code)]
+    /// The total gas paid so far for expanding the memory, so that the next
+    /// expansion only charges the difference.
     last_gas_cost: u64,
 }
 
@@ -37,6 +69,33 @@ impl Memory {
         self.store[offset..offset + size].copy_from_slice(&value);
     }
 
+    /// TrySet sets offset + size to value, failing if the range goes past the
+    /// end of the memory or if value is not `size` bytes long
+    pub fn try_set(&mut self, offset: usize, size: usize, value: &[u8]) -> Result<(), MemoryError> {
+        if value.len() != size {
+            return Err(MemoryError::SizeMismatch {
+                expected: size,
+                actual: value.len(),
+            });
+        }
+        if size == 0 {
+            return Ok(());
+        }
+        self.check_bounds(offset, size)?;
+        self.store[offset..offset + size].copy_from_slice(value);
+        Ok(())
+    }
+
+    /// TrySet sets offset + size to value, like [`Memory::try_set`], with an
+    /// offset popped from the stack
+    pub fn try_set_u256(&mut self, offset: U256, value: &[u8]) -> Result<(), MemoryError> {
+        // Writing nothing is a NO-OP, whatever the offset
+        if value.is_empty() {
+            return Ok(());
+        }
+        self.try_set(to_offset(offset)?, value.len(), value)
+    }
+
     // Set32 sets the 32 bytes starting at offset to the value of val, left-padded
     // with zeroes to 32 bytes.
     pub fn set_32(&mut self, offset: usize, val: U256) {
@@ -51,6 +110,18 @@ impl Memory {
         self.store[offset..offset + 32].copy_from_slice(&b32);
     }
 
+    /// TrySet32 sets the 32 bytes starting at offset to the value of val,
+    /// failing if the range goes past the end of the memory
+    pub fn try_set_32(&mut self, offset: usize, val: U256) -> Result<(), MemoryError> {
+        self.try_set(offset, 32, &val.to_be_bytes())
+    }
+
+    /// TrySet32 sets the 32 bytes starting at offset to the value of val,
+    /// like [`Memory::try_set_32`], with an offset popped from the stack
+    pub fn try_set_32_u256(&mut self, offset: U256, val: U256) -> Result<(), MemoryError> {
+        self.try_set_32(to_offset(offset)?, val)
+    }
+
     /// Resize resizes the memory to size
     ///
     /// NOTE: resizing can only grow the memory, not shrink it.
@@ -58,23 +129,109 @@ impl Memory {
     /// NOTE: resizing must happen before setting memory using other methods
     pub fn resize(&mut self, size: usize) {
         if self.store.len() < size {
-            self.store.resize(size - self.store.len(), 0)
+            self.store.resize(size, 0)
+        }
+    }
+
+    /// GasCost returns the gas to pay for expanding the memory to `new_size`
+    /// bytes, which must be a whole number of words (see
+    /// [`memory_size`](crate::gas::memory_size)). It mirrors geth's
+    /// `memoryGasCost`.
+    ///
+    /// The total cost of `n` words of memory is `3 * n + n * n / 512`: linear
+    /// at first, it becomes quadratic for large memories so that they are
+    /// prohibitively expensive. Only the difference with the cost already paid
+    /// for the current memory is charged, and nothing is charged if the memory
+    /// is already large enough.
+    ///
+    /// NOTE: this records the new total cost, so the memory must then be
+    /// resized to `new_size`.
+    pub fn gas_cost(&mut self, new_size: u64) -> Result<u64, GasUintOverflow> {
+        if new_size == 0 {
+            return Ok(0);
+        }
+        // The square of the number of words would overflow a u64
+        if new_size > MAX_MEMORY_SIZE {
+            return Err(GasUintOverflow);
+        }
+
+        if new_size > self.len() as u64 {
+            let words = to_word_size(new_size);
+            let linear = words * MEMORY_GAS;
+            let quadratic = words * words / QUAD_COEFF_DIV;
+            let total = linear + quadratic;
+
+            let fee = total - self.last_gas_cost;
+            self.last_gas_cost = total;
+            return Ok(fee);
         }
+        Ok(0)
     }
 
     /// GetCopy returns offset + size as a new slice
     pub fn get_copy(&self, offset: usize, size: usize) -> Option<Vec<u8>> {
+        self.get_ptr(offset, size).map(|slice| slice.to_vec())
+    }
+
+    /// GetPtr returns a reference to offset + size, without copying it
+    pub fn get_ptr(&self, offset: usize, size: usize) -> Option<&[u8]> {
         if size == 0 {
             return None;
         }
 
-        if self.len() > offset {
-            return Some(self.store[offset..offset + size].to_vec());
+        if self.check_bounds(offset, size).is_ok() {
+            return Some(&self.store[offset..offset + size]);
         }
 
         None
     }
 
+    /// TryGet returns offset + size as a new slice, failing if the range goes
+    /// past the end of the memory. Unlike [`Memory::get_copy`], reading 0
+    /// bytes returns an empty slice.
+    pub fn try_get(&self, offset: usize, size: usize) -> Result<Vec<u8>, MemoryError> {
+        if size == 0 {
+            return Ok(Vec::new());
+        }
+        self.check_bounds(offset, size)?;
+        Ok(self.store[offset..offset + size].to_vec())
+    }
+
+    /// TryGet returns offset + size as a new slice, like [`Memory::try_get`],
+    /// with an offset and a size popped from the stack
+    pub fn try_get_u256(&self, offset: U256, size: U256) -> Result<Vec<u8>, MemoryError> {
+        // Reading nothing is a NO-OP, whatever the offset
+        if size == 0 {
+            return Ok(Vec::new());
+        }
+        self.try_get(to_offset(offset)?, to_offset(size)?)
+    }
+
+    /// Get32 returns the 32 bytes starting at offset as a word
+    pub fn get_32(&self, offset: usize) -> U256 {
+        // length of store may never be less than offset + size.
+        // The store should be resized PRIOR to reading the memory
+        if offset + 32 > self.store.len() {
+            panic!("invalid memory: store size is {} but will need minimum size of {}; failed to read 32 bytes from {} to {}", self.store.len(), offset + 32, offset, offset + 32);
+        }
+        let mut b32 = [0u8; 32];
+        b32.copy_from_slice(&self.store[offset..offset + 32]);
+        U256::from_be_bytes(b32)
+    }
+
+    /// TryGet32 returns the 32 bytes starting at offset as a word, failing if
+    /// the range goes past the end of the memory
+    pub fn try_get_32(&self, offset: usize) -> Result<U256, MemoryError> {
+        self.check_bounds(offset, 32)?;
+        Ok(self.get_32(offset))
+    }
+
+    /// TryGet32 returns the 32 bytes starting at offset as a word, like
+    /// [`Memory::try_get_32`], with an offset popped from the stack
+    pub fn try_get_32_u256(&self, offset: U256) -> Result<U256, MemoryError> {
+        self.try_get_32(to_offset(offset)?)
+    }
+
     /// Len returns the length of the backing slice
     #[allow(clippy::len_without_is_empty)]
     pub fn len(&self) -> usize {
@@ -92,6 +249,42 @@ impl Memory {
         // copy_within panics if the range is out of bounds
         self.store.copy_within(src..src + len, dst);
     }
+
+    /// TryCopy copies data from the src position slice into the dst position,
+    /// failing if either range goes past the end of the memory.
+    /// The source and destination may overlap.
+    pub fn try_copy(&mut self, dst: usize, src: usize, len: usize) -> Result<(), MemoryError> {
+        if len == 0 {
+            return Ok(());
+        }
+        self.check_bounds(src, len)?;
+        self.check_bounds(dst, len)?;
+        self.store.copy_within(src..src + len, dst);
+        Ok(())
+    }
+
+    /// TryCopy copies data from the src position slice into the dst position,
+    /// like [`Memory::try_copy`], with offsets and a length popped from the
+    /// stack
+    pub fn try_copy_u256(&mut self, dst: U256, src: U256, len: U256) -> Result<(), MemoryError> {
+        // Copying nothing is a NO-OP, whatever the offsets
+        if len == 0 {
+            return Ok(());
+        }
+        self.try_copy(to_offset(dst)?, to_offset(src)?, to_offset(len)?)
+    }
+
+    /// CheckBounds fails if offset + size goes past the end of the memory.
+    fn check_bounds(&self, offset: usize, size: usize) -> Result<(), MemoryError> {
+        match offset.checked_add(size) {
+            Some(end) if end <= self.store.len() => Ok(()),
+            _ => Err(MemoryError::OutOfBounds {
+                offset,
+                size,
+                len: self.store.len(),
+            }),
+        }
+    }
 }
 
 #[cfg(not(tarpaulin_include))]
@@ -121,6 +314,36 @@ mod tests {
         assert_eq!(mem.store.len(), 32);
     }
 
+    #[test]
+    fn can_resize_twice() {
+        let mut mem = Memory::new();
+        mem.resize(32);
+        mem.resize(96);
+        assert_eq!(mem.store.len(), 96);
+    }
+
+    #[test]
+    fn can_get_gas_cost() {
+        let mut mem = Memory::new();
+        assert_eq!(mem.gas_cost(0), Ok(0));
+        // 1 word: 3 * 1 + 1 / 512
+        assert_eq!(mem.gas_cost(32), Ok(3));
+        mem.resize(32);
+        // Already paid for
+        assert_eq!(mem.gas_cost(32), Ok(0));
+        // 1024 words: 3 * 1024 + 1024 * 1024 / 512, minus what was paid
+        assert_eq!(mem.gas_cost(32 * 1024), Ok(3 * 1024 + 2048 - 3));
+        mem.resize(32 * 1024);
+        assert_eq!(mem.last_gas_cost, 3 * 1024 + 2048);
+    }
+
+    #[test]
+    fn cannot_get_gas_cost_when_overflowing() {
+        let mut mem = Memory::new();
+        assert_eq!(mem.gas_cost(MAX_MEMORY_SIZE + 32), Err(GasUintOverflow));
+        assert!(mem.gas_cost(MAX_MEMORY_SIZE).is_ok());
+    }
+
     #[test]
     fn does_not_resize_when_size_is_bigger_than_store_len() {
         let mut mem = Memory::new();
@@ -205,6 +428,177 @@ mod tests {
         assert_eq!(result, None);
     }
 
+    #[test]
+    fn can_get_ptr() {
+        let mut mem = Memory::new();
+        mem.resize(32);
+        mem.set(0, 32, [vec![0x01; 16], vec![0x02; 16]].concat());
+        assert_eq!(
+            mem.get_ptr(8, 16),
+            Some(&[[0x01; 8], [0x02; 8]].concat()[..])
+        );
+        assert_eq!(mem.get_ptr(0, 0), None);
+        assert_eq!(mem.get_ptr(32, 1), None);
+    }
+
+    #[test]
+    fn can_get_32() {
+        let mut mem = Memory::new();
+        mem.resize(64);
+        let test = uint!("0x001122334455667788990011223344556677889900112233445566778899001122");
+        mem.set_32(16, test);
+        assert_eq!(mem.get_32(16), test);
+        assert_eq!(mem.get_32(0), test >> 128);
+    }
+
+    #[test]
+    #[should_panic]
+    fn cannot_get_32_when_memory_too_small() {
+        let mut mem = Memory::new();
+        mem.resize(32);
+        mem.get_32(1);
+    }
+
+    #[test]
+    fn cannot_get_copy_past_the_end() {
+        let mut mem = Memory::new();
+        mem.resize(32);
+        assert_eq!(mem.get_copy(16, 17), None);
+        assert_eq!(mem.get_ptr(16, 17), None);
+        assert_eq!(mem.get_copy(1, usize::MAX), None);
+    }
+
+    #[test]
+    fn can_try_set() {
+        let mut mem = Memory::new();
+        mem.resize(32);
+        assert_eq!(mem.try_set(16, 16, &[0x01; 16]), Ok(()));
+        assert_eq!(mem.store, [vec![0x00; 16], vec![0x01; 16]].concat());
+        assert_eq!(mem.try_set(64, 0, &[]), Ok(()));
+    }
+
+    #[test]
+    fn cannot_try_set_out_of_bounds() {
+        let mut mem = Memory::new();
+        mem.resize(32);
+        assert_eq!(
+            mem.try_set(16, 17, &[0x01; 17]),
+            Err(MemoryError::OutOfBounds {
+                offset: 16,
+                size: 17,
+                len: 32
+            })
+        );
+        assert_eq!(
+            mem.try_set(usize::MAX, 1, &[0x01]),
+            Err(MemoryError::OutOfBounds {
+                offset: usize::MAX,
+                size: 1,
+                len: 32
+            })
+        );
+        assert_eq!(
+            mem.try_set(0, 2, &[0x01]),
+            Err(MemoryError::SizeMismatch {
+                expected: 2,
+                actual: 1
+            })
+        );
+        assert_eq!(mem.store, vec![0x00; 32]);
+    }
+
+    #[test]
+    fn can_try_set_32() {
+        let mut mem = Memory::new();
+        mem.resize(32);
+        assert_eq!(mem.try_set_32(0, uint!("0x01")), Ok(()));
+        assert_eq!(mem.try_get_32(0), Ok(uint!("0x01")));
+        assert!(matches!(
+            mem.try_set_32(1, uint!("0x01")),
+            Err(MemoryError::OutOfBounds { .. })
+        ));
+        assert!(matches!(
+            mem.try_get_32(1),
+            Err(MemoryError::OutOfBounds { .. })
+        ));
+    }
+
+    #[test]
+    fn can_try_get() {
+        let mut mem = Memory::new();
+        mem.resize(32);
+        mem.set(0, 32, [vec![0x01; 16], vec![0x02; 16]].concat());
+        assert_eq!(mem.try_get(16, 16), Ok(vec![0x02; 16]));
+        assert_eq!(mem.try_get(64, 0), Ok(vec![]));
+        assert!(matches!(
+            mem.try_get(16, 17),
+            Err(MemoryError::OutOfBounds { .. })
+        ));
+    }
+
+    #[test]
+    fn can_try_copy() {
+        let mut mem = Memory::new();
+        mem.resize(32);
+        mem.set(0, 32, [vec![0x01; 16], vec![0x02; 16]].concat());
+        assert!(matches!(
+            mem.try_copy(17, 0, 16),
+            Err(MemoryError::OutOfBounds { .. })
+        ));
+        assert!(matches!(
+            mem.try_copy(0, 17, 16),
+            Err(MemoryError::OutOfBounds { .. })
+        ));
+        assert_eq!(mem.try_copy(0, 16, 16), Ok(()));
+        assert_eq!(mem.store, vec![0x02; 32]);
+    }
+
+    #[test]
+    fn cannot_use_u256_offsets_past_u64() {
+        let mut mem = Memory::new();
+        mem.resize(32);
+        let huge = U256::from(u64::MAX) + 1;
+        assert_eq!(
+            mem.try_get_u256(huge, uint!("1")),
+            Err(MemoryError::OffsetOverflow { offset: huge })
+        );
+        assert_eq!(
+            mem.try_get_32_u256(huge),
+            Err(MemoryError::OffsetOverflow { offset: huge })
+        );
+        assert_eq!(
+            mem.try_set_u256(huge, &[0x01]),
+            Err(MemoryError::OffsetOverflow { offset: huge })
+        );
+        assert_eq!(
+            mem.try_set_32_u256(huge, uint!("0x01")),
+            Err(MemoryError::OffsetOverflow { offset: huge })
+        );
+        assert_eq!(
+            mem.try_copy_u256(huge, uint!("0"), uint!("1")),
+            Err(MemoryError::OffsetOverflow { offset: huge })
+        );
+        // Offsets are ignored when nothing is accessed
+        assert_eq!(mem.try_get_u256(huge, uint!("0")), Ok(vec![]));
+        assert_eq!(mem.try_set_u256(huge, &[]), Ok(()));
+        assert_eq!(mem.try_copy_u256(huge, huge, uint!("0")), Ok(()));
+    }
+
+    #[test]
+    fn can_use_u256_offsets() {
+        let mut mem = Memory::new();
+        mem.resize(64);
+        mem.try_set_32_u256(uint!("32"), U256::MAX).unwrap();
+        mem.try_set_u256(uint!("0"), &[0x01]).unwrap();
+        mem.try_copy_u256(uint!("1"), uint!("32"), uint!("2"))
+            .unwrap();
+        assert_eq!(
+            mem.try_get_u256(uint!("0"), uint!("4")),
+            Ok(vec![0x01, 0xff, 0xff, 0x00])
+        );
+        assert_eq!(mem.try_get_32_u256(uint!("32")), Ok(U256::MAX));
+    }
+
     #[test]
     fn can_get_len() {
         let mut mem = Memory::new();
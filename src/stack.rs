@@ -1,4 +1,35 @@
 use ethnum::U256;
+use thiserror::Error;
+
+/// The maximum number of items the stack can hold.
+pub const STACK_LIMIT: usize = 1024;
+
+/// Errors raised when an operation would break the bounds of the stack. The
+/// interpreter turns them into a halt of the execution.
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum StackError {
+    /// The operation would grow the stack to `len` items, which is more than
+    /// [`STACK_LIMIT`].
+    #[error("stack limit reached {len} ({STACK_LIMIT})")]
+    StackOverflow { len: usize },
+    /// The operation needs `required` items on the stack but only `available`
+    /// are there.
+    #[error("stack underflow ({available} <=> {required})")]
+    StackUnderflow { required: usize, available: usize },
+}
+
+/// MinStack returns the minimum number of items an operation popping `pops`
+/// items needs on the stack.
+pub const fn min_stack(pops: usize, _pushes: usize) -> usize {
+    pops
+}
+
+/// MaxStack returns the maximum number of items the stack may hold before an
+/// operation popping `pops` items and pushing `pushes` items runs, so that
+/// the stack still holds at most [`STACK_LIMIT`] items afterwards.
+pub const fn max_stack(pops: usize, pushes: usize) -> usize {
+    STACK_LIMIT + pops - pushes
+}
 
 /// A general purpose stack of 256-bit words. This was chosen because it is
 /// convenient for Ethereum's core cryptographic operations such as Keccak-256
@@ -30,16 +61,38 @@ impl Stack {
     }
 
     /// Push a value onto the stack
-    /// TODO: The maximum size of the stack is 1024 bytes
+    ///
+    /// NOTE: this does not enforce the [`STACK_LIMIT`]. The interpreter
+    /// validates the stack before running each opcode, see [`Stack::validate`],
+    /// otherwise use [`Stack::try_push`].
     pub fn push(&mut self, value: U256) {
         self.data.push(value);
     }
 
+    /// Push a value onto the stack, failing if the stack is full
+    pub fn try_push(&mut self, value: U256) -> Result<(), StackError> {
+        if self.len() >= STACK_LIMIT {
+            return Err(StackError::StackOverflow {
+                len: self.len() + 1,
+            });
+        }
+        self.data.push(value);
+        Ok(())
+    }
+
     /// Pop the topmost element of the stack
     pub fn pop(&mut self) -> Option<U256> {
         self.data.pop()
     }
 
+    /// Pop the topmost element of the stack, failing if the stack is empty
+    pub fn try_pop(&mut self) -> Result<U256, StackError> {
+        self.data.pop().ok_or(StackError::StackUnderflow {
+            required: 1,
+            available: 0,
+        })
+    }
+
     /// Get the number of elements in the stack
     #[allow(clippy::len_without_is_empty)]
     pub fn len(&self) -> usize {
@@ -56,6 +109,15 @@ impl Stack {
         self.data[len - n] = last;
     }
 
+    /// Swap the last element with the nth element from the top, failing if
+    /// the stack holds less than `n` elements.
+    /// Note that `n` starts from 1 (not 0)
+    pub fn try_swap(&mut self, n: usize) -> Result<(), StackError> {
+        self.require(n)?;
+        self.swap(n);
+        Ok(())
+    }
+
     /// Duplicate the nth element from the top
     /// and push it onto the stack.
     /// Note that `n` starts from 1 (not 0)
@@ -65,6 +127,15 @@ impl Stack {
         self.data.push(num);
     }
 
+    /// Duplicate the nth element from the top and push it onto the stack,
+    /// failing if the stack holds less than `n` elements or is full.
+    /// Note that `n` starts from 1 (not 0)
+    pub fn try_dup(&mut self, n: usize) -> Result<(), StackError> {
+        self.require(n)?;
+        let num = self.data[self.len() - n];
+        self.try_push(num)
+    }
+
     /// Get the topmost element of the stack
     pub fn peek(&self) -> Option<&U256> {
         self.data.last()
@@ -73,8 +144,43 @@ impl Stack {
     /// Back returns the n'th item in stack.
     /// Unfortunately, unlike other functions, `n` starts from 0 (not 1).
     pub fn back(&self, n: usize) -> Option<&U256> {
-        let len = self.len();
-        self.data.get(len - n - 1)
+        let index = self.len().checked_sub(n + 1)?;
+        self.data.get(index)
+    }
+
+    /// Back returns the n'th item in stack, failing if the stack holds `n`
+    /// elements or less.
+    /// Unfortunately, unlike other functions, `n` starts from 0 (not 1).
+    pub fn try_back(&self, n: usize) -> Result<&U256, StackError> {
+        self.require(n + 1)?;
+        Ok(&self.data[self.len() - n - 1])
+    }
+
+    /// Validate checks that an operation can run against the stack, given the
+    /// bounds computed by [`min_stack`] and [`max_stack`]. This is the check
+    /// geth's interpreter performs before running each opcode, so that
+    /// handlers never have to deal with a stack that is too short or too long.
+    pub fn validate(&self, min: usize, max: usize) -> Result<(), StackError> {
+        self.require(min)?;
+        if self.len() > max {
+            // `max` is `STACK_LIMIT + pops - pushes`, so the difference is the
+            // number of items the operation adds to the stack.
+            return Err(StackError::StackOverflow {
+                len: self.len() + STACK_LIMIT - max,
+            });
+        }
+        Ok(())
+    }
+
+    /// Require fails if the stack holds less than `n` elements.
+    fn require(&self, n: usize) -> Result<(), StackError> {
+        if self.len() < n {
+            return Err(StackError::StackUnderflow {
+                required: n,
+                available: self.len(),
+            });
+        }
+        Ok(())
     }
 }
 
@@ -230,4 +336,112 @@ mod tests {
         assert_eq!(value, Some(&uint!("0o2")));
         assert_eq!(stack.len(), 3);
     }
+
+    #[test]
+    fn cannot_back_past_bottom() {
+        let mut stack = Stack::new();
+        stack.push(uint!("0o1"));
+        assert_eq!(stack.back(1), None);
+        assert_eq!(
+            stack.try_back(1),
+            Err(StackError::StackUnderflow {
+                required: 2,
+                available: 1
+            })
+        );
+        assert_eq!(stack.try_back(0), Ok(&uint!("0o1")));
+    }
+
+    #[test]
+    fn can_try_push_until_limit() {
+        let mut stack = Stack::new();
+        for _ in 0..STACK_LIMIT {
+            stack.try_push(uint!("0o1")).unwrap();
+        }
+        assert_eq!(
+            stack.try_push(uint!("0o1")),
+            Err(StackError::StackOverflow {
+                len: STACK_LIMIT + 1
+            })
+        );
+        assert_eq!(stack.len(), STACK_LIMIT);
+    }
+
+    #[test]
+    fn cannot_try_pop_empty_stack() {
+        let mut stack = Stack::new();
+        assert_eq!(
+            stack.try_pop(),
+            Err(StackError::StackUnderflow {
+                required: 1,
+                available: 0
+            })
+        );
+    }
+
+    #[test]
+    fn cannot_try_swap_past_bottom() {
+        let mut stack = Stack::new();
+        stack.push(uint!("0o1"));
+        stack.push(uint!("0o2"));
+        assert_eq!(
+            stack.try_swap(3),
+            Err(StackError::StackUnderflow {
+                required: 3,
+                available: 2
+            })
+        );
+        stack.try_swap(2).unwrap();
+        assert_eq!(stack.data(), &[uint!("0o2"), uint!("0o1")]);
+    }
+
+    #[test]
+    fn cannot_try_dup_past_bottom_or_limit() {
+        let mut stack = Stack::new();
+        stack.push(uint!("0o1"));
+        assert_eq!(
+            stack.try_dup(2),
+            Err(StackError::StackUnderflow {
+                required: 2,
+                available: 1
+            })
+        );
+        for _ in 1..STACK_LIMIT {
+            stack.try_dup(1).unwrap();
+        }
+        assert_eq!(
+            stack.try_dup(1),
+            Err(StackError::StackOverflow {
+                len: STACK_LIMIT + 1
+            })
+        );
+    }
+
+    #[test]
+    fn can_validate() {
+        let mut stack = Stack::new();
+        stack.push(uint!("0o1"));
+        // ADD pops 2 items and pushes 1
+        assert_eq!(
+            stack.validate(min_stack(2, 1), max_stack(2, 1)),
+            Err(StackError::StackUnderflow {
+                required: 2,
+                available: 1
+            })
+        );
+        stack.push(uint!("0o2"));
+        assert_eq!(stack.validate(min_stack(2, 1), max_stack(2, 1)), Ok(()));
+
+        // PUSH pops nothing and pushes 1
+        for _ in 2..STACK_LIMIT {
+            stack.push(uint!("0o1"));
+        }
+        assert_eq!(
+            stack.validate(min_stack(0, 1), max_stack(0, 1)),
+            Err(StackError::StackOverflow {
+                len: STACK_LIMIT + 1
+            })
+        );
+        assert_eq!(stack.validate(min_stack(2, 1), max_stack(2, 1)), Ok(()));
+    }
 }
@@ -0,0 +1,848 @@
+use std::{collections::HashMap, hash::Hash};
+
+use ethnum::{uint, U256};
+
+use crate::{
+    access_list::{AccessList, AccessTuple},
+    contract::Address,
+    crypto::keccak256,
+    fork::Fork,
+    journal::{Journal, JournalEntry},
+};
+
+/// The hash of empty code, i.e. the Keccak-256 hash of nothing. It is the code
+/// hash of every account without code.
+pub const EMPTY_CODE_HASH: U256 =
+    uint!("0xc5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470");
+
+/// StateDB is the world state the interpreter runs against: the accounts, with
+/// their balance, nonce, code and storage. It is the counterpart of geth's
+/// `vm.StateDB` interface.
+///
+/// Reading an account which does not exist yields zeros (and no code), and
+/// writing to it creates it.
+pub trait StateDB {
+    /// CreateAccount creates a new account at `address`, or resets the one
+    /// already there. The balance is kept, since an address can be funded
+    /// before a contract is deployed to it.
+    fn create_account(&mut self, address: &Address);
+
+    /// GetBalance returns the balance of the account, in wei.
+    fn get_balance(&self, address: &Address) -> U256;
+    /// AddBalance adds `amount` to the balance of the account.
+    fn add_balance(&mut self, address: &Address, amount: U256);
+    /// SubBalance subtracts `amount` from the balance of the account. Like in
+    /// geth, the caller must have checked that the balance is large enough.
+    fn sub_balance(&mut self, address: &Address, amount: U256);
+
+    /// GetNonce returns the nonce of the account.
+    fn get_nonce(&self, address: &Address) -> u64;
+    /// SetNonce sets the nonce of the account.
+    fn set_nonce(&mut self, address: &Address, nonce: u64);
+
+    /// GetCode returns the code of the account, which is empty if it has none.
+    fn get_code(&self, address: &Address) -> &[u8];
+    /// GetCodeHash returns the Keccak-256 hash of the code of the account, or
+    /// zero if the account does not exist.
+    fn get_code_hash(&self, address: &Address) -> U256;
+    /// GetCodeSize returns the size of the code of the account.
+    fn get_code_size(&self, address: &Address) -> usize {
+        self.get_code(address).len()
+    }
+    /// SetCode sets the code of the account.
+    fn set_code(&mut self, address: &Address, code: Vec<u8>);
+
+    /// GetState returns the value of the storage slot `key` of the account.
+    fn get_state(&self, address: &Address, key: &U256) -> U256;
+    /// SetState sets the value of the storage slot `key` of the account.
+    fn set_state(&mut self, address: &Address, key: U256, value: U256);
+    /// GetCommittedState returns the value the storage slot `key` of the
+    /// account had at the start of the transaction, which SSTORE compares
+    /// against (EIP-2200).
+    fn get_committed_state(&self, address: &Address, key: &U256) -> U256;
+
+    /// GetTransientState returns the value of the transient storage slot `key`
+    /// of the account (EIP-1153).
+    fn get_transient_state(&self, address: &Address, key: &U256) -> U256;
+    /// SetTransientState sets the value of the transient storage slot `key` of
+    /// the account. Transient storage is discarded at the end of the
+    /// transaction.
+    fn set_transient_state(&mut self, address: &Address, key: U256, value: U256);
+
+    /// SelfDestruct marks the account as destructed and clears its balance.
+    /// The account still exists until the end of the transaction.
+    fn self_destruct(&mut self, address: &Address);
+    /// HasSelfDestructed returns true if the account was self-destructed.
+    fn has_self_destructed(&self, address: &Address) -> bool;
+
+    /// Exist returns true if the account exists, even if it is empty or was
+    /// self-destructed.
+    fn exist(&self, address: &Address) -> bool;
+    /// Empty returns true if the account does not exist, or is empty as
+    /// defined by EIP-161: no nonce, no balance and no code.
+    fn empty(&self, address: &Address) -> bool;
+
+    /// Snapshot returns the id of the current revision of the state, which
+    /// can be reverted to with [`StateDB::revert_to_snapshot`].
+    fn snapshot(&mut self) -> usize;
+    /// RevertToSnapshot undoes all the changes made since the snapshot `id`
+    /// was taken.
+    fn revert_to_snapshot(&mut self, id: usize);
+
+    /// AddRefund adds `gas` to the refund counter of the transaction.
+    fn add_refund(&mut self, gas: u64);
+    /// SubRefund removes `gas` from the refund counter of the transaction.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the counter goes below zero, like geth does, since the gas
+    /// rules never take back more than they gave.
+    fn sub_refund(&mut self, gas: u64);
+    /// GetRefund returns the refund counter of the transaction. The refund is
+    /// capped when the transaction ends (see
+    /// [`max_refund`](crate::gas::max_refund)).
+    fn get_refund(&self) -> u64;
+
+    /// AddressInAccessList returns true if the account is warm (EIP-2929).
+    fn address_in_access_list(&self, address: &Address) -> bool;
+    /// SlotInAccessList returns whether the account, and the slot `key` of
+    /// the account, are warm (EIP-2929).
+    fn slot_in_access_list(&self, address: &Address, key: &U256) -> (bool, bool);
+    /// AddAddressToAccessList warms the account up.
+    fn add_address_to_access_list(&mut self, address: &Address);
+    /// AddSlotToAccessList warms the slot `key` of the account up, along with
+    /// the account itself.
+    fn add_slot_to_access_list(&mut self, address: &Address, key: U256);
+
+    /// Prepare warms up what a transaction accesses before it runs, like
+    /// geth's `Prepare`: since Berlin, the sender, the recipient (if the
+    /// transaction is not a creation), the precompiles and the access list of
+    /// the transaction (EIP-2929, EIP-2930); since Shanghai, the coinbase
+    /// (EIP-3651).
+    ///
+    /// It starts the transaction over: the access list and the transient
+    /// storage of the previous transaction are cleared (EIP-1153).
+    fn prepare(
+        &mut self,
+        fork: Fork,
+        sender: &Address,
+        coinbase: &Address,
+        recipient: Option<&Address>,
+        access_list: &[AccessTuple],
+    );
+}
+
+/// Account is the state of a single account.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Account {
+    pub balance: U256,
+    pub nonce: u64,
+    pub code: Vec<u8>,
+    /// The hash of `code`, which is cached since EXTCODEHASH and the
+    /// emptiness check need it
+    pub code_hash: U256,
+    /// The non-zero storage slots
+    pub storage: HashMap<U256, U256>,
+    pub self_destructed: bool,
+}
+
+impl Account {
+    /// IsEmpty returns true if the account is empty as defined by EIP-161.
+    pub fn is_empty(&self) -> bool {
+        self.nonce == 0 && self.balance == 0 && self.code_hash == EMPTY_CODE_HASH
+    }
+}
+
+#[cfg(not(tarpaulin_include))]
+impl Default for Account {
+    fn default() -> Self {
+        Account {
+            balance: U256::ZERO,
+            nonce: 0,
+            code: Vec::new(),
+            code_hash: EMPTY_CODE_HASH,
+            storage: HashMap::new(),
+            self_destructed: false,
+        }
+    }
+}
+
+/// MemoryStateDB is a [`StateDB`] held entirely in memory, e.g. to run code
+/// against a handful of accounts set up by hand.
+///
+/// Every change is recorded in a [`Journal`], so that it can be reverted.
+/// What only lasts for a transaction (e.g. the refund counter) is cleared by
+/// [`MemoryStateDB::finalise`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MemoryStateDB {
+    accounts: HashMap<Address, Account>,
+    journal: Journal,
+    /// The value of the storage slots written in the transaction, before
+    /// their first write. It is geth's `originStorage`.
+    originals: HashMap<(Address, U256), U256>,
+    refund: u64,
+    access_list: AccessList,
+    /// The non-zero transient storage slots (EIP-1153)
+    transient: HashMap<(Address, U256), U256>,
+    /// The accounts and storage slots accessed in the transaction, along with
+    /// its access list. Unlike `access_list`, it is not journaled, so that it
+    /// keeps the accesses of the calls which reverted.
+    accessed: AccessList,
+}
+
+impl MemoryStateDB {
+    pub fn new() -> Self {
+        MemoryStateDB::default()
+    }
+
+    /// Get the account at `address`, if it exists
+    pub fn account(&self, address: &Address) -> Option<&Account> {
+        self.accounts.get(address)
+    }
+
+    /// Iterate over the accounts, in no particular order
+    pub fn accounts(&self) -> impl Iterator<Item = (&Address, &Account)> {
+        self.accounts.iter()
+    }
+
+    /// Get the changes made to the state since the journal was reset, e.g.
+    /// to compute a state diff
+    pub fn journal(&self) -> &Journal {
+        &self.journal
+    }
+
+    /// Get the accounts and storage slots which are warm (EIP-2929)
+    pub fn access_list(&self) -> &AccessList {
+        &self.access_list
+    }
+
+    /// Get the accounts and storage slots accessed in the transaction, even by
+    /// calls which reverted, along with the access list of the transaction.
+    /// This is what geth's `AccessListTracer` collects for
+    /// `eth_createAccessList`, so that the transaction can be sent again with
+    /// this access list.
+    pub fn accessed(&self) -> &AccessList {
+        &self.accessed
+    }
+
+    /// Finalise ends the transaction, like geth's `Finalise`: the
+    /// self-destructed accounts are deleted, and the changes made so far
+    /// cannot be reverted anymore. The refund counter, the access list and
+    /// the transient storage start over for the next transaction.
+    pub fn finalise(&mut self) {
+        self.accounts.retain(|_, account| !account.self_destructed);
+        self.journal.reset();
+        self.originals.clear();
+        self.refund = 0;
+        self.access_list = AccessList::new();
+        self.transient.clear();
+        self.accessed = AccessList::new();
+    }
+
+    /// GetOrNewAccount returns the account at `address`, creating it if it does
+    /// not exist. It is geth's `getOrNewStateObject`.
+    fn get_or_new_account(&mut self, address: &Address) -> &mut Account {
+        if !self.accounts.contains_key(address) {
+            self.journal.append(JournalEntry::CreateAccount {
+                address: *address,
+                prev: None,
+            });
+        }
+        self.accounts.entry(*address).or_default()
+    }
+
+    /// SetBalance sets the balance of an account which exists.
+    fn set_balance(&mut self, address: &Address, balance: U256) {
+        let account = self.account_mut(address);
+        let prev = std::mem::replace(&mut account.balance, balance);
+        self.journal.append(JournalEntry::Balance {
+            address: *address,
+            prev,
+        });
+    }
+
+    /// Undo reverts a single change.
+    fn undo(&mut self, entry: JournalEntry) {
+        match entry {
+            JournalEntry::CreateAccount { address, prev } => match prev {
+                Some(prev) => {
+                    self.accounts.insert(address, *prev);
+                }
+                None => {
+                    self.accounts.remove(&address);
+                }
+            },
+            JournalEntry::Balance { address, prev } => {
+                self.account_mut(&address).balance = prev;
+            }
+            JournalEntry::Nonce { address, prev } => {
+                self.account_mut(&address).nonce = prev;
+            }
+            JournalEntry::Code { address, prev } => {
+                let account = self.account_mut(&address);
+                account.code_hash = U256::from_be_bytes(keccak256(&prev));
+                account.code = prev;
+            }
+            JournalEntry::Storage { address, key, prev } => {
+                set_slot(&mut self.account_mut(&address).storage, key, prev);
+            }
+            JournalEntry::SelfDestruct {
+                address,
+                prev,
+                prev_balance,
+            } => {
+                let account = self.account_mut(&address);
+                account.self_destructed = prev;
+                account.balance = prev_balance;
+            }
+            JournalEntry::TransientStorage { address, key, prev } => {
+                set_slot(&mut self.transient, (address, key), prev);
+            }
+            JournalEntry::Refund { prev } => self.refund = prev,
+            JournalEntry::AccessListAddAccount { address } => {
+                self.access_list.delete_address(&address);
+            }
+            JournalEntry::AccessListAddSlot { address, key } => {
+                self.access_list.delete_slot(&address, &key);
+            }
+        }
+    }
+
+    /// AccountMut returns the account a journal entry applies to. The entries
+    /// are undone in reverse, so the account exists until its creation is.
+    fn account_mut(&mut self, address: &Address) -> &mut Account {
+        self.accounts
+            .get_mut(address)
+            .expect("journaled accounts exist until their creation is undone")
+    }
+}
+
+/// SetSlot sets a storage slot, only keeping non-zero slots since all the
+/// others read as zero.
+fn set_slot<K: Eq + Hash>(storage: &mut HashMap<K, U256>, key: K, value: U256) {
+    if value == 0 {
+        storage.remove(&key);
+    } else {
+        storage.insert(key, value);
+    }
+}
+
+impl StateDB for MemoryStateDB {
+    fn create_account(&mut self, address: &Address) {
+        let balance = self.get_balance(address);
+        let prev = self.accounts.remove(address).map(Box::new);
+        self.journal.append(JournalEntry::CreateAccount {
+            address: *address,
+            prev,
+        });
+        self.accounts.insert(
+            *address,
+            Account {
+                balance,
+                ..Account::default()
+            },
+        );
+    }
+
+    fn get_balance(&self, address: &Address) -> U256 {
+        self.accounts
+            .get(address)
+            .map_or(U256::ZERO, |account| account.balance)
+    }
+
+    fn add_balance(&mut self, address: &Address, amount: U256) {
+        let balance = self.get_or_new_account(address).balance;
+        self.set_balance(address, balance.wrapping_add(amount));
+    }
+
+    fn sub_balance(&mut self, address: &Address, amount: U256) {
+        let balance = self.get_or_new_account(address).balance;
+        self.set_balance(address, balance.wrapping_sub(amount));
+    }
+
+    fn get_nonce(&self, address: &Address) -> u64 {
+        self.accounts
+            .get(address)
+            .map_or(0, |account| account.nonce)
+    }
+
+    fn set_nonce(&mut self, address: &Address, nonce: u64) {
+        let account = self.get_or_new_account(address);
+        let prev = std::mem::replace(&mut account.nonce, nonce);
+        self.journal.append(JournalEntry::Nonce {
+            address: *address,
+            prev,
+        });
+    }
+
+    fn get_code(&self, address: &Address) -> &[u8] {
+        self.accounts
+            .get(address)
+            .map_or(&[], |account| account.code.as_slice())
+    }
+
+    fn get_code_hash(&self, address: &Address) -> U256 {
+        self.accounts
+            .get(address)
+            .map_or(U256::ZERO, |account| account.code_hash)
+    }
+
+    fn set_code(&mut self, address: &Address, code: Vec<u8>) {
+        let account = self.get_or_new_account(address);
+        account.code_hash = U256::from_be_bytes(keccak256(&code));
+        let prev = std::mem::replace(&mut account.code, code);
+        self.journal.append(JournalEntry::Code {
+            address: *address,
+            prev,
+        });
+    }
+
+    fn get_state(&self, address: &Address, key: &U256) -> U256 {
+        self.accounts
+            .get(address)
+            .and_then(|account| account.storage.get(key))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn set_state(&mut self, address: &Address, key: U256, value: U256) {
+        let storage = &mut self.get_or_new_account(address).storage;
+        let prev = storage.get(&key).copied().unwrap_or_default();
+        set_slot(storage, key, value);
+        self.originals.entry((*address, key)).or_insert(prev);
+        self.journal.append(JournalEntry::Storage {
+            address: *address,
+            key,
+            prev,
+        });
+    }
+
+    fn get_committed_state(&self, address: &Address, key: &U256) -> U256 {
+        match self.originals.get(&(*address, *key)) {
+            Some(value) => *value,
+            None => self.get_state(address, key),
+        }
+    }
+
+    fn get_transient_state(&self, address: &Address, key: &U256) -> U256 {
+        self.transient
+            .get(&(*address, *key))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn set_transient_state(&mut self, address: &Address, key: U256, value: U256) {
+        let prev = self.get_transient_state(address, &key);
+        if prev == value {
+            return;
+        }
+        self.journal.append(JournalEntry::TransientStorage {
+            address: *address,
+            key,
+            prev,
+        });
+        set_slot(&mut self.transient, (*address, key), value);
+    }
+
+    fn self_destruct(&mut self, address: &Address) {
+        if let Some(account) = self.accounts.get_mut(address) {
+            self.journal.append(JournalEntry::SelfDestruct {
+                address: *address,
+                prev: account.self_destructed,
+                prev_balance: account.balance,
+            });
+            account.self_destructed = true;
+            account.balance = U256::ZERO;
+        }
+    }
+
+    fn has_self_destructed(&self, address: &Address) -> bool {
+        self.accounts
+            .get(address)
+            .map_or(false, |account| account.self_destructed)
+    }
+
+    fn exist(&self, address: &Address) -> bool {
+        self.accounts.contains_key(address)
+    }
+
+    fn empty(&self, address: &Address) -> bool {
+        self.accounts.get(address).map_or(true, Account::is_empty)
+    }
+
+    fn snapshot(&mut self) -> usize {
+        self.journal.snapshot()
+    }
+
+    fn revert_to_snapshot(&mut self, id: usize) {
+        let entries: Vec<_> = self.journal.revert_to_snapshot(id).collect();
+        for entry in entries {
+            self.undo(entry);
+        }
+    }
+
+    fn add_refund(&mut self, gas: u64) {
+        self.journal
+            .append(JournalEntry::Refund { prev: self.refund });
+        self.refund += gas;
+    }
+
+    fn sub_refund(&mut self, gas: u64) {
+        self.journal
+            .append(JournalEntry::Refund { prev: self.refund });
+        self.refund = self.refund.checked_sub(gas).unwrap_or_else(|| {
+            panic!(
+                "refund counter below zero (gas: {gas} > refund: {})",
+                self.refund
+            )
+        });
+    }
+
+    fn get_refund(&self) -> u64 {
+        self.refund
+    }
+
+    fn address_in_access_list(&self, address: &Address) -> bool {
+        self.access_list.contains_address(address)
+    }
+
+    fn slot_in_access_list(&self, address: &Address, key: &U256) -> (bool, bool) {
+        self.access_list.contains(address, key)
+    }
+
+    fn add_address_to_access_list(&mut self, address: &Address) {
+        self.accessed.add_address(*address);
+        if self.access_list.add_address(*address) {
+            self.journal
+                .append(JournalEntry::AccessListAddAccount { address: *address });
+        }
+    }
+
+    fn add_slot_to_access_list(&mut self, address: &Address, key: U256) {
+        self.accessed.add_slot(*address, key);
+        let (address_added, slot_added) = self.access_list.add_slot(*address, key);
+        // The account is added first, so it is removed last when reverting
+        if address_added {
+            self.journal
+                .append(JournalEntry::AccessListAddAccount { address: *address });
+        }
+        if slot_added {
+            self.journal.append(JournalEntry::AccessListAddSlot {
+                address: *address,
+                key,
+            });
+        }
+    }
+
+    fn prepare(
+        &mut self,
+        fork: Fork,
+        sender: &Address,
+        coinbase: &Address,
+        recipient: Option<&Address>,
+        access_list: &[AccessTuple],
+    ) {
+        self.access_list = AccessList::new();
+        self.transient.clear();
+        self.accessed = AccessList::new();
+        for tuple in access_list {
+            self.accessed.add_address(tuple.address);
+            for key in &tuple.storage_keys {
+                self.accessed.add_slot(tuple.address, *key);
+            }
+        }
+        if fork < Fork::Berlin {
+            return;
+        }
+        // Like in geth, warming up is not journaled: the transaction cannot
+        // revert to before it started.
+        self.access_list.add_address(*sender);
+        if let Some(recipient) = recipient {
+            self.access_list.add_address(*recipient);
+        }
+        for precompile in fork.precompiles() {
+            self.access_list.add_address(precompile);
+        }
+        for tuple in access_list {
+            self.access_list.add_address(tuple.address);
+            for key in &tuple.storage_keys {
+                self.access_list.add_slot(tuple.address, *key);
+            }
+        }
+        if fork >= Fork::Shanghai {
+            self.access_list.add_address(*coinbase);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALICE: Address = [0xaa; 20];
+    const BOB: Address = [0xbb; 20];
+
+    #[test]
+    fn can_read_missing_account() {
+        let state = MemoryStateDB::new();
+        assert_eq!(state.get_balance(&ALICE), U256::ZERO);
+        assert_eq!(state.get_nonce(&ALICE), 0);
+        assert!(state.get_code(&ALICE).is_empty());
+        assert_eq!(state.get_code_hash(&ALICE), U256::ZERO);
+        assert_eq!(state.get_state(&ALICE, &uint!("1")), U256::ZERO);
+        assert!(!state.exist(&ALICE));
+        assert!(state.empty(&ALICE));
+    }
+
+    #[test]
+    fn can_update_balance_and_nonce() {
+        let mut state = MemoryStateDB::new();
+        state.add_balance(&ALICE, uint!("100"));
+        state.sub_balance(&ALICE, uint!("30"));
+        assert_eq!(state.get_balance(&ALICE), uint!("70"));
+        assert!(state.exist(&ALICE));
+        assert!(!state.empty(&ALICE));
+
+        state.set_nonce(&BOB, 1);
+        assert_eq!(state.get_nonce(&BOB), 1);
+        assert!(!state.empty(&BOB));
+    }
+
+    #[test]
+    fn can_set_code() {
+        let mut state = MemoryStateDB::new();
+        state.create_account(&ALICE);
+        assert_eq!(state.get_code_hash(&ALICE), EMPTY_CODE_HASH);
+        assert!(state.empty(&ALICE));
+
+        state.set_code(&ALICE, vec![0x00]);
+        assert_eq!(state.get_code(&ALICE), &[0x00]);
+        assert_eq!(state.get_code_size(&ALICE), 1);
+        assert_eq!(
+            state.get_code_hash(&ALICE),
+            U256::from_be_bytes(keccak256(&[0x00]))
+        );
+        assert!(!state.empty(&ALICE));
+    }
+
+    #[test]
+    fn can_set_state() {
+        let mut state = MemoryStateDB::new();
+        state.set_state(&ALICE, uint!("1"), uint!("42"));
+        assert_eq!(state.get_state(&ALICE, &uint!("1")), uint!("42"));
+        assert_eq!(state.get_state(&BOB, &uint!("1")), U256::ZERO);
+
+        state.set_state(&ALICE, uint!("1"), U256::ZERO);
+        assert_eq!(state.get_state(&ALICE, &uint!("1")), U256::ZERO);
+        assert!(state.account(&ALICE).unwrap().storage.is_empty());
+    }
+
+    #[test]
+    fn can_create_account_keeping_balance() {
+        let mut state = MemoryStateDB::new();
+        state.add_balance(&ALICE, uint!("5"));
+        state.set_nonce(&ALICE, 3);
+        state.set_state(&ALICE, uint!("1"), uint!("1"));
+        state.create_account(&ALICE);
+        assert_eq!(state.get_balance(&ALICE), uint!("5"));
+        assert_eq!(state.get_nonce(&ALICE), 0);
+        assert_eq!(state.get_state(&ALICE, &uint!("1")), U256::ZERO);
+    }
+
+    #[test]
+    fn can_revert_to_snapshot() {
+        let mut state = MemoryStateDB::new();
+        state.add_balance(&ALICE, uint!("10"));
+        state.set_state(&ALICE, uint!("1"), uint!("1"));
+        let snapshot = state.snapshot();
+        let before = state.clone();
+
+        state.sub_balance(&ALICE, uint!("4"));
+        state.set_nonce(&ALICE, 1);
+        state.set_code(&ALICE, vec![0x00]);
+        state.set_state(&ALICE, uint!("1"), U256::ZERO);
+        state.set_state(&ALICE, uint!("2"), uint!("2"));
+        state.add_balance(&BOB, uint!("4"));
+        state.self_destruct(&ALICE);
+        state.create_account(&ALICE);
+
+        state.revert_to_snapshot(snapshot);
+        assert_eq!(state.accounts, before.accounts);
+        assert!(!state.exist(&BOB));
+        assert_eq!(state.get_code_hash(&ALICE), EMPTY_CODE_HASH);
+    }
+
+    #[test]
+    fn can_revert_nested_snapshots() {
+        let mut state = MemoryStateDB::new();
+        let outer = state.snapshot();
+        state.set_state(&ALICE, uint!("1"), uint!("1"));
+        let inner = state.snapshot();
+        state.set_state(&ALICE, uint!("1"), uint!("2"));
+
+        state.revert_to_snapshot(inner);
+        assert_eq!(state.get_state(&ALICE, &uint!("1")), uint!("1"));
+        state.revert_to_snapshot(outer);
+        assert_eq!(state.get_state(&ALICE, &uint!("1")), U256::ZERO);
+        assert!(!state.exist(&ALICE));
+    }
+
+    #[test]
+    fn can_iterate_journal() {
+        let mut state = MemoryStateDB::new();
+        state.add_balance(&ALICE, uint!("1"));
+        state.set_state(&BOB, uint!("1"), uint!("1"));
+        let touched: Vec<_> = state
+            .journal()
+            .entries()
+            .iter()
+            .filter_map(|entry| entry.address().copied())
+            .collect();
+        assert_eq!(touched, vec![ALICE, ALICE, BOB, BOB]);
+        assert!(matches!(
+            state.journal().entries()[3],
+            JournalEntry::Storage { prev, .. } if prev == 0
+        ));
+
+        state.finalise();
+        assert!(state.journal().is_empty());
+    }
+
+    #[test]
+    fn can_get_committed_state() {
+        let mut state = MemoryStateDB::new();
+        state.set_state(&ALICE, uint!("1"), uint!("1"));
+        state.finalise();
+
+        state.set_state(&ALICE, uint!("1"), uint!("2"));
+        state.set_state(&ALICE, uint!("1"), uint!("3"));
+        assert_eq!(state.get_state(&ALICE, &uint!("1")), uint!("3"));
+        assert_eq!(state.get_committed_state(&ALICE, &uint!("1")), uint!("1"));
+        assert_eq!(state.get_committed_state(&ALICE, &uint!("2")), U256::ZERO);
+
+        state.finalise();
+        assert_eq!(state.get_committed_state(&ALICE, &uint!("1")), uint!("3"));
+    }
+
+    #[test]
+    fn can_revert_refund_and_access_list() {
+        let mut state = MemoryStateDB::new();
+        state.add_refund(10);
+        state.add_address_to_access_list(&ALICE);
+        let snapshot = state.snapshot();
+        state.sub_refund(4);
+        state.add_slot_to_access_list(&ALICE, uint!("1"));
+        state.add_slot_to_access_list(&BOB, uint!("1"));
+        assert_eq!(state.get_refund(), 6);
+        assert_eq!(state.slot_in_access_list(&BOB, &uint!("1")), (true, true));
+
+        state.revert_to_snapshot(snapshot);
+        assert_eq!(state.get_refund(), 10);
+        assert_eq!(
+            state.slot_in_access_list(&ALICE, &uint!("1")),
+            (true, false)
+        );
+        assert!(!state.address_in_access_list(&BOB));
+
+        state.finalise();
+        assert_eq!(state.get_refund(), 0);
+        assert!(!state.address_in_access_list(&ALICE));
+    }
+
+    #[test]
+    fn can_set_transient_state() {
+        let mut state = MemoryStateDB::new();
+        state.set_transient_state(&ALICE, uint!("1"), uint!("1"));
+        let snapshot = state.snapshot();
+        state.set_transient_state(&ALICE, uint!("1"), uint!("2"));
+        state.set_transient_state(&BOB, uint!("1"), uint!("3"));
+        assert_eq!(state.get_transient_state(&ALICE, &uint!("1")), uint!("2"));
+        // Transient storage is not storage, and does not create accounts
+        assert_eq!(state.get_state(&ALICE, &uint!("1")), U256::ZERO);
+        assert!(!state.exist(&BOB));
+
+        state.revert_to_snapshot(snapshot);
+        assert_eq!(state.get_transient_state(&ALICE, &uint!("1")), uint!("1"));
+        assert_eq!(state.get_transient_state(&BOB, &uint!("1")), U256::ZERO);
+
+        state.finalise();
+        assert_eq!(state.get_transient_state(&ALICE, &uint!("1")), U256::ZERO);
+    }
+
+    #[test]
+    fn can_prepare_access_list() {
+        let coinbase = [0xcc; 20];
+        let precompile = |i| {
+            let mut address = [0u8; 20];
+            address[19] = i;
+            address
+        };
+        let tuples = [AccessTuple {
+            address: [0xdd; 20],
+            storage_keys: vec![uint!("1")],
+        }];
+
+        let mut state = MemoryStateDB::new();
+        state.prepare(Fork::Cancun, &ALICE, &coinbase, Some(&BOB), &tuples);
+        assert!(state.address_in_access_list(&ALICE));
+        assert!(state.address_in_access_list(&BOB));
+        assert!(state.address_in_access_list(&coinbase));
+        assert!(state.address_in_access_list(&precompile(0x0a)));
+        assert!(!state.address_in_access_list(&precompile(0x0b)));
+        assert_eq!(
+            state.slot_in_access_list(&[0xdd; 20], &uint!("1")),
+            (true, true)
+        );
+        // Only the access list of the transaction counts as accessed
+        assert_eq!(state.accessed().tuples(&[]), tuples);
+
+        // The coinbase is only warm since Shanghai
+        let mut state = MemoryStateDB::new();
+        state.prepare(Fork::London, &ALICE, &coinbase, None, &[]);
+        assert!(!state.address_in_access_list(&coinbase));
+        assert!(state.address_in_access_list(&precompile(0x09)));
+
+        // Nothing is warm before Berlin
+        let mut state = MemoryStateDB::new();
+        state.prepare(Fork::Istanbul, &ALICE, &coinbase, Some(&BOB), &tuples);
+        assert!(state.access_list().tuples(&[]).is_empty());
+    }
+
+    #[test]
+    fn can_prepare_next_transaction() {
+        let mut state = MemoryStateDB::new();
+        state.set_transient_state(&ALICE, uint!("1"), uint!("2"));
+        state.add_slot_to_access_list(&BOB, uint!("1"));
+        state.prepare(Fork::Istanbul, &ALICE, &ALICE, None, &[]);
+        assert_eq!(state.get_transient_state(&ALICE, &uint!("1")), 0);
+        assert!(!state.address_in_access_list(&BOB));
+
+        // Warming up is not journaled, so it cannot be reverted
+        let len = state.journal().len();
+        state.prepare(Fork::Berlin, &ALICE, &ALICE, Some(&BOB), &[]);
+        assert_eq!(state.journal().len(), len);
+    }
+
+    #[test]
+    #[should_panic(expected = "refund counter below zero")]
+    fn cannot_sub_refund_below_zero() {
+        MemoryStateDB::new().sub_refund(1);
+    }
+
+    #[test]
+    fn can_self_destruct() {
+        let mut state = MemoryStateDB::new();
+        state.add_balance(&ALICE, uint!("5"));
+        state.self_destruct(&ALICE);
+        assert!(state.has_self_destructed(&ALICE));
+        assert_eq!(state.get_balance(&ALICE), U256::ZERO);
+        // The account lives until the end of the transaction
+        assert!(state.exist(&ALICE));
+        state.finalise();
+        assert!(!state.exist(&ALICE));
+
+        // Destructing a missing account does nothing
+        state.self_destruct(&BOB);
+        assert!(!state.exist(&BOB));
+    }
+}
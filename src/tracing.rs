@@ -0,0 +1,224 @@
+use std::io::Write;
+
+use ethnum::U256;
+use serde_json::json;
+
+use crate::{contract::Contract, interpreter::ExecutionError, opcodes::Opcode};
+
+/// Step is the state of the machine before an instruction runs, as given to
+/// the tracers.
+#[derive(Clone, Copy, Debug)]
+pub struct Step<'a> {
+    /// The offset of the instruction in the code
+    pub pc: usize,
+    /// The byte of the instruction, which may not be a valid opcode
+    pub op: u8,
+    /// The gas available before the instruction runs
+    pub gas: u64,
+    /// The gas charged for the instruction, including memory expansion. It
+    /// only covers what was charged before a fault.
+    pub cost: u64,
+    /// The depth of the call, starting from 1. The interpreter only runs a
+    /// single call frame, without nested calls, so it is always 1 for now.
+    pub depth: usize,
+    /// The stack, bottom first
+    pub stack: &'a [U256],
+    /// The memory, before it is expanded for the instruction
+    pub memory: &'a [u8],
+    /// The data returned by the last call. Since the interpreter cannot make
+    /// calls yet, it is always empty for now.
+    pub return_data: &'a [u8],
+    /// The gas refunded at the end of the transaction so far
+    pub refund: u64,
+}
+
+impl Step<'_> {
+    /// OpName returns the name of the opcode, or None if the byte is not a
+    /// valid opcode.
+    pub fn op_name(&self) -> Option<String> {
+        Opcode::try_from(self.op).ok().map(|op| op.to_string())
+    }
+}
+
+/// Tracer observes an execution, step by step. It is the counterpart of
+/// geth's `EVMLogger`. Every hook does nothing by default.
+pub trait Tracer {
+    /// CaptureStart is called once, before the first instruction runs.
+    fn capture_start(&mut self, _code: &[u8], _contract: &Contract, _gas: u64) {}
+
+    /// CaptureState is called before each instruction runs, once it was paid
+    /// for.
+    fn capture_state(&mut self, _step: &Step) {}
+
+    /// CaptureFault is called instead of [`Tracer::capture_state`] when an
+    /// instruction fails, either before or while running.
+    fn capture_fault(&mut self, _step: &Step, _err: &ExecutionError) {}
+
+    /// CaptureEnd is called once the execution is over, with the returned (or
+    /// reverted) data and the gas used, which includes all the gas if an
+    /// error occurred.
+    fn capture_end(&mut self, _output: &[u8], _gas_used: u64, _err: Option<&ExecutionError>) {}
+}
+
+/// NoopTracer ignores the execution.
+pub struct NoopTracer;
+
+impl Tracer for NoopTracer {}
+
+/// Eip3155Logger writes the trace of an execution as JSON lines, in the
+/// format of EIP-3155, so that it can be compared with the traces of other
+/// clients (e.g. `evm --json run` in geth). Each step is a line, and the
+/// execution ends with a summary line.
+pub struct Eip3155Logger<W: Write> {
+    writer: W,
+    memory: bool,
+}
+
+impl<W: Write> Eip3155Logger<W> {
+    pub fn new(writer: W) -> Self {
+        Eip3155Logger {
+            writer,
+            memory: false,
+        }
+    }
+
+    /// Also print the content of the memory at each step, which is optional
+    /// in EIP-3155 and can be large.
+    pub fn with_memory(mut self) -> Self {
+        self.memory = true;
+        self
+    }
+
+    /// Get back the writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn log(&mut self, step: &Step, err: Option<&ExecutionError>) {
+        let mut line = json!({
+            "pc": step.pc,
+            "op": step.op,
+            "gas": format!("{:#x}", step.gas),
+            "gasCost": format!("{:#x}", step.cost),
+            "memSize": step.memory.len(),
+            "stack": step.stack.iter().map(|word| format!("{word:#x}")).collect::<Vec<_>>(),
+            "returnData": format!("0x{}", hex::encode(step.return_data)),
+            "depth": step.depth,
+            "refund": step.refund,
+            "opName": step.op_name(),
+        });
+        if self.memory {
+            line["memory"] = format!("0x{}", hex::encode(step.memory)).into();
+        }
+        if let Some(err) = err {
+            line["error"] = err.to_string().into();
+        }
+        self.write(&line);
+    }
+
+    /// Write a JSON line. Like geth's logger, the trace is best effort, so
+    /// failing to write it does not fail the execution.
+    fn write(&mut self, line: &serde_json::Value) {
+        writeln!(self.writer, "{line}").ok();
+    }
+}
+
+impl<W: Write> Tracer for Eip3155Logger<W> {
+    fn capture_state(&mut self, step: &Step) {
+        self.log(step, None);
+    }
+
+    fn capture_fault(&mut self, step: &Step, err: &ExecutionError) {
+        self.log(step, Some(err));
+    }
+
+    fn capture_end(&mut self, output: &[u8], gas_used: u64, err: Option<&ExecutionError>) {
+        let mut line = json!({
+            "output": hex::encode(output),
+            "gasUsed": format!("{gas_used:#x}"),
+        });
+        if let Some(err) = err {
+            line["error"] = err.to_string().into();
+        }
+        self.write(&line);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::*;
+    use crate::interpreter::Interpreter;
+
+    fn trace(code: &[u8], gas: u64) -> Vec<Value> {
+        let mut logger = Eip3155Logger::new(Vec::new()).with_memory();
+        Interpreter::new(code, gas)
+            .run_with_tracer(&mut logger)
+            .ok();
+        String::from_utf8(logger.into_inner())
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn can_log_steps() {
+        // PUSH1 0x2a, PUSH0, MSTORE, PUSH1 0x20, PUSH0, RETURN
+        let lines = trace(&[0x60, 0x2a, 0x5f, 0x52, 0x60, 0x20, 0x5f, 0xf3], 100);
+        assert_eq!(lines.len(), 7);
+        assert_eq!(
+            lines[0],
+            json!({
+                "pc": 0,
+                "op": 0x60,
+                "gas": "0x64",
+                "gasCost": "0x3",
+                "memSize": 0,
+                "memory": "0x",
+                "stack": [],
+                "returnData": "0x",
+                "depth": 1,
+                "refund": 0,
+                "opName": "PUSH1",
+            })
+        );
+        // MSTORE pays for a word of memory, and is logged before expanding
+        assert_eq!(lines[2]["opName"], "MSTORE");
+        assert_eq!(lines[2]["gasCost"], "0x6");
+        assert_eq!(lines[2]["stack"], json!(["0x2a", "0x0"]));
+        assert_eq!(lines[2]["memSize"], 0);
+        assert_eq!(lines[3]["memSize"], 32);
+        assert_eq!(
+            lines[6],
+            json!({
+                "output": format!("{:064x}", 0x2a),
+                "gasUsed": "0x10",
+            })
+        );
+    }
+
+    #[test]
+    fn can_log_fault() {
+        // PUSH1 0x01, ADD
+        let lines = trace(&[0x60, 0x01, 0x01], 100);
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[1]["opName"], "ADD");
+        assert_eq!(lines[1]["gas"], "0x61");
+        assert!(lines[1]["error"]
+            .as_str()
+            .unwrap()
+            .contains("stack underflow"));
+        assert_eq!(lines[2]["gasUsed"], "0x64");
+        assert!(lines[2]["error"].is_string());
+    }
+
+    #[test]
+    fn can_log_invalid_opcode() {
+        let lines = trace(&[0x0c], 100);
+        assert_eq!(lines[0]["op"], 0x0c);
+        assert_eq!(lines[0]["opName"], Value::Null);
+        assert!(lines[0]["error"].is_string());
+    }
+}